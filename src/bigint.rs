@@ -0,0 +1,436 @@
+//! Arbitrary precision integers backing the exact number types.
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+
+const BASE: u64 = 1 << 32;
+const DECIMAL_CHUNK: u32 = 1_000_000_000;
+
+/// Largest number of bits of a power computed exactly, about 19 700 decimal digits.
+pub const MAX_POW_BITS: u64 = 1 << 16;
+
+/// Sign-magnitude integer. The magnitude is stored as little endian 32 bit words
+/// without trailing zeros, so zero is an empty vector and comparisons can be derived.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    neg: bool,
+    mag: Vec<u32>,
+}
+
+impl BigInt {
+    pub fn zero() -> BigInt {
+        BigInt::default()
+    }
+
+    pub fn one() -> BigInt {
+        BigInt::from_u64(1)
+    }
+
+    pub fn from_u64(v: u64) -> BigInt {
+        BigInt::from_mag(false, vec![v as u32, (v >> 32) as u32])
+    }
+
+    pub fn from_i64(v: i64) -> BigInt {
+        let mut ret = BigInt::from_u64(v.unsigned_abs());
+        ret.neg = v < 0 && !ret.is_zero();
+        ret
+    }
+
+    fn from_mag(neg: bool, mut mag: Vec<u32>) -> BigInt {
+        while mag.last() == Some(&0) {
+            mag.pop();
+        }
+        let neg = neg && !mag.is_empty();
+        BigInt { neg, mag }
+    }
+
+    /// Parses a non-empty string of decimal digits.
+    pub fn parse_digits(digits: &str) -> Option<BigInt> {
+        if digits.is_empty() || !digits.bytes().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        let mut ret = BigInt::zero();
+        let head = digits.len() % 9;
+        let mut chunks = vec![&digits[..head]];
+        chunks.extend(digits.as_bytes()[head..].chunks(9).map(|c| ::std::str::from_utf8(c).unwrap()));
+        for chunk in chunks.into_iter().filter(|c| !c.is_empty()) {
+            let mul = 10u32.pow(chunk.len() as u32);
+            ret = ret.mul_small(mul, chunk.parse().unwrap());
+        }
+        Some(ret)
+    }
+
+    /// Parses a decimal literal such as `12.375` into its digits as an integer (`12375`)
+    /// and the number of digits after the decimal point (`3`).
+    pub fn parse_decimal(literal: &str) -> Option<(BigInt, usize)> {
+        let (int, frac) = match literal.find('.') {
+            Some(idx) => (&literal[..idx], &literal[idx + 1..]),
+            None => (literal, ""),
+        };
+        BigInt::parse_digits(&format!("{}{}", int, frac)).map(|digits| (digits, frac.len()))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mag.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.neg
+    }
+
+    pub fn is_odd(&self) -> bool {
+        self.mag.first().is_some_and(|w| w & 1 == 1)
+    }
+
+    pub fn abs(&self) -> BigInt {
+        BigInt::from_mag(false, self.mag.clone())
+    }
+
+    pub fn neg(&self) -> BigInt {
+        BigInt::from_mag(!self.neg, self.mag.clone())
+    }
+
+    /// Number of significant bits of the magnitude.
+    pub fn bits(&self) -> u64 {
+        match self.mag.last() {
+            Some(top) => self.mag.len() as u64 * 32 - u64::from(top.leading_zeros()),
+            None => 0,
+        }
+    }
+
+    pub fn add(&self, rhs: &BigInt) -> BigInt {
+        if self.neg == rhs.neg {
+            return BigInt::from_mag(self.neg, add_mag(&self.mag, &rhs.mag));
+        }
+        match cmp_mag(&self.mag, &rhs.mag) {
+            Ordering::Less => BigInt::from_mag(rhs.neg, sub_mag(&rhs.mag, &self.mag)),
+            _ => BigInt::from_mag(self.neg, sub_mag(&self.mag, &rhs.mag)),
+        }
+    }
+
+    pub fn sub(&self, rhs: &BigInt) -> BigInt {
+        self.add(&rhs.neg())
+    }
+
+    pub fn mul(&self, rhs: &BigInt) -> BigInt {
+        let mut out = vec![0u32; self.mag.len() + rhs.mag.len()];
+        for (i, &a) in self.mag.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in rhs.mag.iter().enumerate() {
+                let t = u64::from(a) * u64::from(b) + u64::from(out[i + j]) + carry;
+                out[i + j] = t as u32;
+                carry = t >> 32;
+            }
+            out[i + rhs.mag.len()] = carry as u32;
+        }
+        BigInt::from_mag(self.neg != rhs.neg, out)
+    }
+
+    // self * mul + add, on magnitudes
+    fn mul_small(&self, mul: u32, add: u32) -> BigInt {
+        let mut out = Vec::with_capacity(self.mag.len() + 1);
+        let mut carry = u64::from(add);
+        for &w in &self.mag {
+            let t = u64::from(w) * u64::from(mul) + carry;
+            out.push(t as u32);
+            carry = t >> 32;
+        }
+        out.push(carry as u32);
+        BigInt::from_mag(self.neg, out)
+    }
+
+    // quotient and remainder of the magnitude divided by a single word
+    fn divrem_small(&self, div: u32) -> (BigInt, u32) {
+        let mut out = vec![0u32; self.mag.len()];
+        let mut rem = 0u64;
+        for i in (0..self.mag.len()).rev() {
+            let t = rem * BASE + u64::from(self.mag[i]);
+            out[i] = (t / u64::from(div)) as u32;
+            rem = t % u64::from(div);
+        }
+        (BigInt::from_mag(self.neg, out), rem as u32)
+    }
+
+    /// Truncating division, the remainder takes the sign of the dividend.
+    /// Returns `None` when dividing by zero.
+    pub fn divrem(&self, rhs: &BigInt) -> Option<(BigInt, BigInt)> {
+        if rhs.is_zero() {
+            return None;
+        }
+        if cmp_mag(&self.mag, &rhs.mag) == Ordering::Less {
+            return Some((BigInt::zero(), self.clone()));
+        }
+        if rhs.mag.len() == 1 {
+            let (q, r) = self.divrem_small(rhs.mag[0]);
+            return Some((BigInt::from_mag(self.neg != rhs.neg, q.mag), BigInt::from_mag(self.neg, vec![r])));
+        }
+
+        let (q, r) = divrem_mag(&self.mag, &rhs.mag);
+        Some((BigInt::from_mag(self.neg != rhs.neg, q), BigInt::from_mag(self.neg, r)))
+    }
+
+    /// Greatest common divisor by Lehmer's algorithm, which replaces most multi-word divisions
+    /// by single-word steps on the leading bits (Knuth, TAOCP vol. 2, 4.5.2, algorithm L).
+    pub fn gcd(&self, rhs: &BigInt) -> BigInt {
+        let (mut a, mut b) = (self.abs(), rhs.abs());
+        if a < b {
+            ::std::mem::swap(&mut a, &mut b);
+        }
+        while !b.is_zero() {
+            if a.mag.len() <= 2 {
+                let (mut x, mut y) = (a.to_u128() as u64, b.to_u128() as u64);
+                while y != 0 {
+                    let r = x % y;
+                    x = y;
+                    y = r;
+                }
+                return BigInt::from_u64(x);
+            }
+
+            // the leading 62 bits of a and the bits of b at the same position keep the
+            // cosequence below 2^62, so the updates below fit in i64
+            let shift = a.bits() - 62;
+            let (mut x, mut y) = (i128::from(a.bits_at(shift)), i128::from(b.bits_at(shift)));
+            let (mut ca, mut cb, mut cc, mut cd) = (1i128, 0i128, 0i128, 1i128);
+            while y + cc != 0 && y + cd != 0 {
+                let q = (x + ca) / (y + cc);
+                if q != (x + cb) / (y + cd) {
+                    break;
+                }
+                (ca, cc) = (cc, ca - q * cc);
+                (cb, cd) = (cd, cb - q * cd);
+                (x, y) = (y, x - q * y);
+            }
+
+            if cb == 0 {
+                let r = a.divrem(&b).unwrap().1;
+                a = b;
+                b = r;
+            } else {
+                let word = |v: i128| BigInt::from_i64(v as i64);
+                let next_a = a.mul(&word(ca)).add(&b.mul(&word(cb)));
+                b = a.mul(&word(cc)).add(&b.mul(&word(cd)));
+                a = next_a;
+            }
+        }
+        a
+    }
+
+    /// Whether `self^exp` has more than `MAX_POW_BITS` bits, judged by a lower bound of its size.
+    pub fn pow_too_large(&self, exp: u64) -> bool {
+        self.bits().saturating_sub(1).saturating_mul(exp) > MAX_POW_BITS
+    }
+
+    pub fn pow(&self, mut exp: u64) -> BigInt {
+        let mut base = self.clone();
+        let mut ret = BigInt::one();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                ret = ret.mul(&base);
+            }
+            base = base.mul(&base);
+            exp >>= 1;
+        }
+        ret
+    }
+
+    pub fn shl(&self, bits: u64) -> BigInt {
+        let words = (bits / 32) as usize;
+        let bits = (bits % 32) as u32;
+        let mut out = vec![0u32; words];
+        let mut carry = 0u32;
+        for &w in &self.mag {
+            out.push(if bits == 0 { w } else { (w << bits) | carry });
+            carry = if bits == 0 { 0 } else { w >> (32 - bits) };
+        }
+        out.push(carry);
+        BigInt::from_mag(self.neg, out)
+    }
+
+    /// Converts to `i64` when the value fits.
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.mag.len() > 2 {
+            return None;
+        }
+        let m = self.mag.iter().rev().fold(0u64, |acc, &w| (acc << 32) | u64::from(w));
+        if self.neg {
+            0i64.checked_sub_unsigned(m)
+        } else {
+            i64::try_from(m).ok()
+        }
+    }
+
+    // the 64 bits of the magnitude starting at bit `shift`
+    fn bits_at(&self, shift: u64) -> u64 {
+        let word = (shift / 32) as usize;
+        let v = (0..3).rev().fold(0u128, |acc, i| (acc << 32) | u128::from(*self.mag.get(word + i).unwrap_or(&0)));
+        (v >> (shift % 32)) as u64
+    }
+
+    // magnitude as u128, only used for values known to fit
+    fn to_u128(&self) -> u128 {
+        self.mag.iter().rev().fold(0u128, |acc, &w| (acc << 32) | u128::from(w))
+    }
+
+    /// Nearest `f64` to the quotient `self / rhs`.
+    pub fn ratio_to_f64(&self, rhs: &BigInt) -> f64 {
+        if self.is_zero() {
+            return 0.0;
+        }
+        // scale so that the quotient carries 65 significant bits, then undo the scaling
+        let shift = 65 + rhs.bits() as i64 - self.bits() as i64;
+        let (n, d) = if shift >= 0 {
+            (self.abs().shl(shift as u64), rhs.abs())
+        } else {
+            (self.abs(), rhs.abs().shl((-shift) as u64))
+        };
+        let (q, r) = n.divrem(&d).unwrap();
+        // a sticky bit below the rounding position keeps round-to-nearest correct
+        let q = q.to_u128() | u128::from(!r.is_zero());
+        let half = -shift / 2;
+        let mut ret = q as f64 * 2f64.powi(half as i32) * 2f64.powi((-shift - half) as i32);
+        if self.neg != rhs.neg {
+            ret = -ret;
+        }
+        ret
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, rhs: &BigInt) -> Ordering {
+        match (self.neg, rhs.neg) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_mag(&self.mag, &rhs.mag),
+            (true, true) => cmp_mag(&rhs.mag, &self.mag),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, rhs: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(rhs))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        let mut chunks = Vec::new();
+        let mut v = self.abs();
+        while !v.is_zero() {
+            let (q, r) = v.divrem_small(DECIMAL_CHUNK);
+            chunks.push(r);
+            v = q;
+        }
+
+        let mut s = String::from(if self.neg { "-" } else { "" });
+        s.push_str(&chunks.pop().unwrap().to_string());
+        for c in chunks.iter().rev() {
+            s.push_str(&format!("{:09}", c));
+        }
+        f.pad(&s)
+    }
+}
+
+fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let (a, b) = if a.len() < b.len() { (b, a) } else { (a, b) };
+    let mut out = Vec::with_capacity(a.len() + 1);
+    let mut carry = 0u64;
+    for (i, &w) in a.iter().enumerate() {
+        let t = u64::from(w) + u64::from(*b.get(i).unwrap_or(&0)) + carry;
+        out.push(t as u32);
+        carry = t >> 32;
+    }
+    out.push(carry as u32);
+    out
+}
+
+// a - b, requires |a| >= |b|
+fn sub_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, &w) in a.iter().enumerate() {
+        let mut t = i64::from(w) - i64::from(*b.get(i).unwrap_or(&0)) - borrow;
+        borrow = 0;
+        if t < 0 {
+            t += BASE as i64;
+            borrow = 1;
+        }
+        out.push(t as u32);
+    }
+    out
+}
+
+// quotient and remainder of magnitudes by word-based long division (Knuth, TAOCP vol. 2,
+// 4.3.1, algorithm D), requires at least two words in `v` and `u` no shorter than `v`
+fn divrem_mag(u: &[u32], v: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    // normalize so that the top word of the divisor has its high bit set
+    let s = v[v.len() - 1].leading_zeros();
+    let shifted = |w: &[u32], extra: usize| {
+        let mut out = vec![0u32; w.len() + extra];
+        for (i, &x) in w.iter().enumerate() {
+            out[i] |= x << s;
+            if s > 0 && i + 1 < out.len() {
+                out[i + 1] |= x >> (32 - s);
+            }
+        }
+        out
+    };
+    let vn = shifted(v, 0);
+    let mut un = shifted(u, 1);
+    let (n, m) = (v.len(), u.len() - v.len());
+    let mut q = vec![0u32; m + 1];
+
+    for j in (0..=m).rev() {
+        // estimate the quotient word from the top two words, it is at most two too large
+        let top = (u64::from(un[j + n]) << 32) | u64::from(un[j + n - 1]);
+        let mut qhat = top / u64::from(vn[n - 1]);
+        let mut rhat = top % u64::from(vn[n - 1]);
+        while qhat >= BASE || qhat * u64::from(vn[n - 2]) > (rhat << 32) + u64::from(un[j + n - 2]) {
+            qhat -= 1;
+            rhat += u64::from(vn[n - 1]);
+            if rhat >= BASE {
+                break;
+            }
+        }
+
+        // un[j..=j+n] -= qhat * vn
+        let (mut carry, mut borrow) = (0u64, 0i64);
+        for i in 0..n {
+            let p = qhat * u64::from(vn[i]) + carry;
+            carry = p >> 32;
+            let t = i64::from(un[i + j]) - (p & 0xFFFF_FFFF) as i64 - borrow;
+            un[i + j] = t.rem_euclid(BASE as i64) as u32;
+            borrow = i64::from(t < 0);
+        }
+        let t = i64::from(un[j + n]) - carry as i64 - borrow;
+        un[j + n] = t.rem_euclid(BASE as i64) as u32;
+
+        // the estimate was one too large, add the divisor back
+        if t < 0 {
+            qhat -= 1;
+            let mut carry = 0u64;
+            for i in 0..n {
+                let t = u64::from(un[i + j]) + u64::from(vn[i]) + carry;
+                un[i + j] = t as u32;
+                carry = t >> 32;
+            }
+            un[j + n] = un[j + n].wrapping_add(carry as u32);
+        }
+        q[j] = qhat as u32;
+    }
+
+    // undo the normalization of the remainder
+    let r = (0..n).map(|i| if s == 0 { un[i] } else { (un[i] >> s) | (un[i + 1] << (32 - s)) }).collect();
+    (q, r)
+}
@@ -0,0 +1,204 @@
+//! Complex numbers for complex-valued evaluation.
+
+use error::Result;
+use number::Number;
+use std::f64::consts;
+use std::fmt;
+use std::str::FromStr;
+
+/// Complex number with `f64` real and imaginary parts.
+///
+/// All built-in functions return the principal value, so `sqrt(-1)` is `i`
+/// and `ln(-2)` is `ln(2) + i*pi`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Complex {
+        Complex { re, im }
+    }
+
+    /// Modulus (absolute value).
+    pub fn norm(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    /// Argument (phase angle) in the range `(-pi, pi]`.
+    pub fn arg(self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    fn conj(self) -> Complex {
+        Complex::new(self.re, -self.im)
+    }
+
+    fn add(self, rhs: Complex) -> Complex {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+
+    fn sub(self, rhs: Complex) -> Complex {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+
+    fn mul(self, rhs: Complex) -> Complex {
+        Complex::new(self.re * rhs.re - self.im * rhs.im, self.re * rhs.im + self.im * rhs.re)
+    }
+
+    fn div(self, rhs: Complex) -> Complex {
+        let d = rhs.re * rhs.re + rhs.im * rhs.im;
+        Complex::new((self.re * rhs.re + self.im * rhs.im) / d, (self.im * rhs.re - self.re * rhs.im) / d)
+    }
+
+    fn scale(self, k: f64) -> Complex {
+        Complex::new(self.re * k, self.im * k)
+    }
+
+    fn exp(self) -> Complex {
+        Complex::new(self.im.cos(), self.im.sin()).scale(self.re.exp())
+    }
+
+    fn ln(self) -> Complex {
+        Complex::new(self.norm().ln(), self.arg())
+    }
+
+    fn sqrt(self) -> Complex {
+        let r = self.norm();
+        Complex::new(((r + self.re) / 2.0).sqrt(), ((r - self.re) / 2.0).sqrt().copysign(self.im))
+    }
+
+    // multiplication by i
+    fn rot(self) -> Complex {
+        Complex::new(-self.im, self.re)
+    }
+
+    fn asin(self) -> Complex {
+        // -i * ln(iz + sqrt(1 - z^2))
+        let one = Complex::new(1.0, 0.0);
+        self.rot().add(one.sub(self.mul(self)).sqrt()).ln().rot().scale(-1.0)
+    }
+
+    fn atan(self) -> Complex {
+        // i/2 * (ln(1 - iz) - ln(1 + iz))
+        let one = Complex::new(1.0, 0.0);
+        one.sub(self.rot()).ln().sub(one.add(self.rot()).ln()).rot().scale(0.5)
+    }
+}
+
+impl From<f64> for Complex {
+    fn from(re: f64) -> Complex {
+        Complex::new(re, 0.0)
+    }
+}
+
+impl fmt::Display for Complex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.im.is_sign_negative() {
+            write!(f, "{}-{}i", self.re, -self.im)
+        } else {
+            write!(f, "{}+{}i", self.re, self.im)
+        }
+    }
+}
+
+impl Number for Complex {
+    fn parse(literal: &str) -> Result<Complex> { Ok(Complex::from(f64::from_str(literal)?)) }
+
+    fn  add(self, rhs: Complex) -> Result<Complex> { Ok(Complex::add(self, rhs)) }
+    fn  sub(self, rhs: Complex) -> Result<Complex> { Ok(Complex::sub(self, rhs)) }
+    fn  mul(self, rhs: Complex) -> Result<Complex> { Ok(Complex::mul(self, rhs)) }
+    fn  div(self, rhs: Complex) -> Result<Complex> { Ok(Complex::div(self, rhs)) }
+    // 0 - x keeps a zero imaginary part positive, so that -1 stays on the upper side of branch cuts
+    fn neg(self)                -> Result<Complex> { Ok(Complex::new(0.0 - self.re, 0.0 - self.im)) }
+
+    fn fmod(self, rhs: Complex) -> Result<Complex> {
+        // a - b*trunc(a/b), which is fmod for real operands
+        let q = Complex::div(self, rhs);
+        Ok(Complex::sub(self, Complex::mul(rhs, Complex::new(q.re.trunc(), q.im.trunc()))))
+    }
+
+    fn pow(self, rhs: Complex) -> Result<Complex> {
+        if self == Complex::default() {
+            // 0^z is 0 only towards a positive real part; 0^-x blows up and the rest is undefined
+            return Ok(if rhs == Complex::default() {
+                Complex::from(1.0)
+            } else if rhs.re > 0.0 {
+                Complex::default()
+            } else if rhs.re < 0.0 && rhs.im == 0.0 {
+                Complex::from(f64::INFINITY)
+            } else {
+                Complex::from(f64::NAN)
+            });
+        }
+        Ok(Complex::mul(rhs, Complex::ln(self)).exp())
+    }
+
+    fn  e() -> Result<Complex> { Ok(Complex::from(consts::E))  }
+    fn pi() -> Result<Complex> { Ok(Complex::from(consts::PI)) }
+    fn  i() -> Result<Complex> { Ok(Complex::new(0.0, 1.0))    }
+
+    fn  abs(self) -> Result<Complex> { Ok(Complex::from(self.norm())) }
+    fn acos(self) -> Result<Complex> { Ok(Complex::from(consts::FRAC_PI_2).sub(Complex::asin(self))) }
+    fn asin(self) -> Result<Complex> { Ok(Complex::asin(self)) }
+    fn atan(self) -> Result<Complex> { Ok(Complex::atan(self)) }
+
+    fn atan2(self, rhs: Complex) -> Result<Complex> {
+        // -i * ln((x + iy) / sqrt(x^2 + y^2)), with y = self and x = rhs
+        let z = Complex::add(rhs, self.rot());
+        let r = Complex::add(rhs.mul(rhs), self.mul(self)).sqrt();
+        Ok(z.div(r).ln().rot().scale(-1.0))
+    }
+
+    fn  ceil(self) -> Result<Complex> { Ok(Complex::new(self.re.ceil(), self.im.ceil())) }
+    fn floor(self) -> Result<Complex> { Ok(Complex::new(self.re.floor(), self.im.floor())) }
+
+    fn cos(self) -> Result<Complex> {
+        Ok(Complex::new(self.re.cos() * self.im.cosh(), -self.re.sin() * self.im.sinh()))
+    }
+
+    fn cosh(self) -> Result<Complex> {
+        Ok(Complex::new(self.re.cosh() * self.im.cos(), self.re.sinh() * self.im.sin()))
+    }
+
+    fn   exp(self) -> Result<Complex> { Ok(Complex::exp(self)) }
+    fn    ln(self) -> Result<Complex> { Ok(Complex::ln(self)) }
+    fn   log(self) -> Result<Complex> { Ok(Complex::ln(self).scale(1.0 / consts::LN_10)) }
+    fn log10(self) -> Result<Complex> { Ok(Complex::ln(self).scale(1.0 / consts::LN_10)) }
+
+    fn sin(self) -> Result<Complex> {
+        Ok(Complex::new(self.re.sin() * self.im.cosh(), self.re.cos() * self.im.sinh()))
+    }
+
+    fn sinh(self) -> Result<Complex> {
+        Ok(Complex::new(self.re.sinh() * self.im.cos(), self.re.cosh() * self.im.sin()))
+    }
+
+    fn sqrt(self) -> Result<Complex> { Ok(Complex::sqrt(self)) }
+
+    fn tan(self) -> Result<Complex> {
+        let d = (2.0 * self.re).cos() + (2.0 * self.im).cosh();
+        Ok(Complex::new((2.0 * self.re).sin() / d, (2.0 * self.im).sinh() / d))
+    }
+
+    fn tanh(self) -> Result<Complex> {
+        let d = (2.0 * self.re).cosh() + (2.0 * self.im).cos();
+        Ok(Complex::new((2.0 * self.re).sinh() / d, (2.0 * self.im).sin() / d))
+    }
+
+    fn   re(self) -> Result<Complex> { Ok(Complex::from(self.re)) }
+    fn   im(self) -> Result<Complex> { Ok(Complex::from(self.im)) }
+    fn  arg(self) -> Result<Complex> { Ok(Complex::from(Complex::arg(self))) }
+    fn conj(self) -> Result<Complex> { Ok(Complex::conj(self)) }
+
+    fn round(self) -> Result<Complex> { Ok(Complex::new(self.re.round(), self.im.round())) }
+    fn trunc(self) -> Result<Complex> { Ok(Complex::new(self.re.trunc(), self.im.trunc())) }
+    fn fract(self) -> Result<Complex> { Ok(Complex::new(self.re.fract(), self.im.fract())) }
+
+    fn sign(self) -> Result<Complex> {
+        // point on the unit circle in the direction of self
+        let r = self.norm();
+        Ok(if r == 0.0 { self } else { self.scale(1.0 / r) })
+    }
+}
@@ -0,0 +1,317 @@
+//! Decimal fixed-point numbers for currency calculations.
+
+use bigint::{BigInt, MAX_POW_BITS};
+use error::{Result, TinyExprError};
+use number::{integral, unsupported, Number};
+use number_theory;
+use std::cmp::Ordering;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Rounding mode used by `Decimal` whenever a result has more digits than its scale.
+pub trait Rounding: Clone + Copy + Default + fmt::Debug + PartialEq {
+    /// Decides whether a quotient truncated towards zero is rounded away from zero instead.
+    /// `half` compares the discarded remainder with half of the last kept digit.
+    fn round_away(half: Ordering, odd: bool) -> bool;
+}
+
+/// Rounds to the nearest value, ties to the even neighbour (banker's rounding).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HalfEven;
+
+/// Rounds to the nearest value, ties away from zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HalfUp;
+
+/// Rounds towards zero, discarding extra digits.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Down;
+
+impl Rounding for HalfEven {
+    fn round_away(half: Ordering, odd: bool) -> bool {
+        half == Ordering::Greater || (half == Ordering::Equal && odd)
+    }
+}
+
+impl Rounding for HalfUp {
+    fn round_away(half: Ordering, _: bool) -> bool {
+        half != Ordering::Less
+    }
+}
+
+impl Rounding for Down {
+    fn round_away(_: Ordering, _: bool) -> bool {
+        false
+    }
+}
+
+/// Decimal number with `SCALE` digits after the decimal point, rounded with `R`.
+///
+/// Literals are read as decimals rather than through `f64`, so `0.1+0.2` is exactly `0.3`.
+/// Addition, subtraction, `%`, `abs`, `ceil` and `floor` are exact, while multiplication,
+/// division, integer powers and literals with more than `SCALE` decimals are rounded
+/// to `SCALE` digits. `round` and `roundto` use `R` as well, so `round(2.5)` is `2` with
+/// the default half-even mode. Other functions are reported as `TinyExprError::Unsupported`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate tinyexpr;
+/// use tinyexpr::decimal::{Decimal, HalfUp};
+///
+/// let share = tinyexpr::interp_as::<Decimal<2, HalfUp>>("10.25/2").unwrap();
+/// assert_eq!(share.to_string(), "5.13");
+///
+/// // the default rounding mode is half-even
+/// let share = tinyexpr::interp_as::<Decimal<2>>("10.25/2").unwrap();
+/// assert_eq!(share.to_string(), "5.12");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Decimal<const SCALE: u32, R: Rounding = HalfEven> {
+    // value multiplied by 10^SCALE
+    mantissa: BigInt,
+    rounding: PhantomData<R>,
+}
+
+impl<const SCALE: u32, R: Rounding> Decimal<SCALE, R> {
+    fn from_mantissa(mantissa: BigInt) -> Decimal<SCALE, R> {
+        Decimal { mantissa, rounding: PhantomData }
+    }
+
+    fn one() -> BigInt {
+        BigInt::from_u64(10).pow(u64::from(SCALE))
+    }
+
+    // self^n by squaring and multiplying, each product rounded using R, or its reciprocal
+    fn pow_rounded(self, n: u64, reciprocal: bool) -> Result<Decimal<SCALE, R>> {
+        let negative = self.mantissa.is_negative() && n & 1 == 1;
+        match Decimal::<SCALE, R>::pow_steps(self, n)? {
+            Some(power) if reciprocal => Number::div(Decimal::from_mantissa(Decimal::<SCALE, R>::one()), power),
+            Some(power) => Ok(power),
+            // the reciprocal of a huge power is below the last digit, which R may still round up
+            None if reciprocal => {
+                let last = if R::round_away(Ordering::Less, false) { BigInt::one() } else { BigInt::zero() };
+                Ok(Decimal::from_mantissa(if negative { last.neg() } else { last }))
+            },
+            None => Err(TinyExprError::Other(String::from("result of pow is too large"))),
+        }
+    }
+
+    // self^n rounded at each step, `None` once it passes `MAX_POW_BITS`
+    fn pow_steps(self, mut n: u64) -> Result<Option<Decimal<SCALE, R>>> {
+        let mut base = self;
+        let mut ret = Decimal::from_mantissa(Decimal::<SCALE, R>::one());
+        while n > 0 {
+            if n & 1 == 1 {
+                ret = Number::mul(ret, base.clone())?;
+            }
+            n >>= 1;
+            if n > 0 {
+                base = Number::mul(base.clone(), base)?;
+            }
+            if ret.mantissa.bits() > MAX_POW_BITS || base.mantissa.bits() > MAX_POW_BITS {
+                return Ok(None);
+            }
+        }
+        Ok(Some(ret))
+    }
+
+    // n / d, rounded to an integer using R
+    fn round(n: &BigInt, d: &BigInt) -> Result<BigInt> {
+        let (q, r) = match n.divrem(d) {
+            Some(qr) => qr,
+            None => return Err(TinyExprError::DivisionByZero),
+        };
+        if r.is_zero() {
+            return Ok(q);
+        }
+        if R::round_away(r.abs().shl(1).cmp(&d.abs()), q.is_odd()) {
+            let step = if n.is_negative() != d.is_negative() { BigInt::from_i64(-1) } else { BigInt::one() };
+            Ok(q.add(&step))
+        } else {
+            Ok(q)
+        }
+    }
+
+    /// Nearest `f64` to this value.
+    pub fn to_f64(&self) -> f64 {
+        self.mantissa.ratio_to_f64(&Decimal::<SCALE, R>::one())
+    }
+}
+
+/// Formats with exactly `SCALE` decimals.
+impl<const SCALE: u32, R: Rounding> fmt::Display for Decimal<SCALE, R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = format!("{:0>width$}", self.mantissa.abs().to_string(), width = SCALE as usize + 1);
+        let (int, frac) = digits.split_at(digits.len() - SCALE as usize);
+        let sign = if self.mantissa.is_negative() { "-" } else { "" };
+        if SCALE == 0 {
+            f.pad(&format!("{}{}", sign, int))
+        } else {
+            f.pad(&format!("{}{}.{}", sign, int, frac))
+        }
+    }
+}
+
+impl<const SCALE: u32, R: Rounding> Number for Decimal<SCALE, R> {
+    fn parse(literal: &str) -> Result<Decimal<SCALE, R>> {
+        let (digits, scale) = match BigInt::parse_decimal(literal) {
+            Some(parsed) => parsed,
+            None => return Err(TinyExprError::Other(format!("invalid number literal `{}`", literal))),
+        };
+        let mantissa = if scale <= SCALE as usize {
+            digits.mul(&BigInt::from_u64(10).pow((SCALE as usize - scale) as u64))
+        } else {
+            Decimal::<SCALE, R>::round(&digits, &BigInt::from_u64(10).pow((scale - SCALE as usize) as u64))?
+        };
+        Ok(Decimal::from_mantissa(mantissa))
+    }
+
+    fn add(self, rhs: Decimal<SCALE, R>) -> Result<Decimal<SCALE, R>> {
+        Ok(Decimal::from_mantissa(self.mantissa.add(&rhs.mantissa)))
+    }
+
+    fn sub(self, rhs: Decimal<SCALE, R>) -> Result<Decimal<SCALE, R>> {
+        Ok(Decimal::from_mantissa(self.mantissa.sub(&rhs.mantissa)))
+    }
+
+    fn mul(self, rhs: Decimal<SCALE, R>) -> Result<Decimal<SCALE, R>> {
+        let n = self.mantissa.mul(&rhs.mantissa);
+        Ok(Decimal::from_mantissa(Decimal::<SCALE, R>::round(&n, &Decimal::<SCALE, R>::one())?))
+    }
+
+    fn div(self, rhs: Decimal<SCALE, R>) -> Result<Decimal<SCALE, R>> {
+        let n = self.mantissa.mul(&Decimal::<SCALE, R>::one());
+        Ok(Decimal::from_mantissa(Decimal::<SCALE, R>::round(&n, &rhs.mantissa)?))
+    }
+
+    fn fmod(self, rhs: Decimal<SCALE, R>) -> Result<Decimal<SCALE, R>> {
+        // both operands share the scale, so the remainder of the mantissas is exact
+        match self.mantissa.divrem(&rhs.mantissa) {
+            Some((_, r)) => Ok(Decimal::from_mantissa(r)),
+            None => Err(TinyExprError::DivisionByZero),
+        }
+    }
+
+    fn neg(self) -> Result<Decimal<SCALE, R>> {
+        Ok(Decimal::from_mantissa(self.mantissa.neg()))
+    }
+
+    fn pow(self, rhs: Decimal<SCALE, R>) -> Result<Decimal<SCALE, R>> {
+        let (exp, r) = rhs.mantissa.divrem(&Decimal::<SCALE, R>::one()).unwrap();
+        if !r.is_zero() {
+            return unsupported("pow with a non-integer exponent");
+        }
+        let n = match exp.abs().to_i64() {
+            Some(n) => n as u64,
+            None => return Err(TinyExprError::Other(String::from("exponent is too large"))),
+        };
+
+        // an exact power would be too large, so it is rounded at each step
+        if self.mantissa.pow_too_large(n) {
+            return Decimal::<SCALE, R>::pow_rounded(self, n, exp.is_negative());
+        }
+
+        // (m / 10^s)^n is m^n / 10^(s*n), and its reciprocal 10^(s*n) / m^n, rounded once
+        let ten = BigInt::from_u64(10);
+        let m = self.mantissa.pow(n);
+        let mantissa = if exp.is_negative() {
+            Decimal::<SCALE, R>::round(&ten.pow(u64::from(SCALE) * (n + 1)), &m)?
+        } else {
+            Decimal::<SCALE, R>::round(&m, &ten.pow(u64::from(SCALE) * n.saturating_sub(1)))?
+        };
+        Ok(Decimal::from_mantissa(if n == 0 { Decimal::<SCALE, R>::one() } else { mantissa }))
+    }
+
+    fn abs(self) -> Result<Decimal<SCALE, R>> {
+        Ok(Decimal::from_mantissa(self.mantissa.abs()))
+    }
+
+    fn ceil(self) -> Result<Decimal<SCALE, R>> {
+        Number::neg(Number::floor(Number::neg(self)?)?)
+    }
+
+    fn floor(self) -> Result<Decimal<SCALE, R>> {
+        let one = Decimal::<SCALE, R>::one();
+        let (mut q, r) = self.mantissa.divrem(&one).unwrap();
+        if r.is_negative() {
+            q = q.sub(&BigInt::one());
+        }
+        Ok(Decimal::from_mantissa(q.mul(&one)))
+    }
+
+    // rounding to a position rounds the tie or the discarded digits using R, like every other result
+    fn round(self) -> Result<Decimal<SCALE, R>> {
+        let one = Decimal::<SCALE, R>::one();
+        Ok(Decimal::from_mantissa(Decimal::<SCALE, R>::round(&self.mantissa, &one)?.mul(&one)))
+    }
+
+    fn round_digits(self, digits: Decimal<SCALE, R>) -> Result<Decimal<SCALE, R>> {
+        let (digits, r) = digits.mantissa.divrem(&Decimal::<SCALE, R>::one()).unwrap();
+        if !r.is_zero() {
+            return unsupported("round with a non-integer number of digits");
+        }
+        // the position of the last kept digit within the mantissa
+        let shift = match BigInt::from_u64(u64::from(SCALE)).sub(&digits).to_i64() {
+            Some(shift) if shift <= 0 => return Ok(self),
+            Some(shift) => shift as u64,
+            None => return Err(TinyExprError::Other(String::from("number of digits is too large"))),
+        };
+        let ten = BigInt::from_u64(10);
+        if ten.pow_too_large(shift) {
+            // far less than half of the step, which R rounds to zero unless it rounds everything up
+            return if self.mantissa.is_zero() || !R::round_away(Ordering::Less, false) {
+                Ok(Decimal::default())
+            } else {
+                Err(TinyExprError::Other(String::from("result of round is too large")))
+            };
+        }
+        let step = ten.pow(shift);
+        Ok(Decimal::from_mantissa(Decimal::<SCALE, R>::round(&self.mantissa, &step)?.mul(&step)))
+    }
+
+    fn roundto(self, step: Decimal<SCALE, R>) -> Result<Decimal<SCALE, R>> {
+        Ok(Decimal::from_mantissa(Decimal::<SCALE, R>::round(&self.mantissa, &step.mantissa)?.mul(&step.mantissa)))
+    }
+
+    fn     e()                        -> Result<Decimal<SCALE, R>> { unsupported("e")     }
+    fn    pi()                        -> Result<Decimal<SCALE, R>> { unsupported("pi")    }
+    fn  acos(self)                    -> Result<Decimal<SCALE, R>> { unsupported("acos")  }
+    fn  asin(self)                    -> Result<Decimal<SCALE, R>> { unsupported("asin")  }
+    fn  atan(self)                    -> Result<Decimal<SCALE, R>> { unsupported("atan")  }
+    fn atan2(self, _: Decimal<SCALE, R>) -> Result<Decimal<SCALE, R>> { unsupported("atan2") }
+    fn   cos(self)                    -> Result<Decimal<SCALE, R>> { unsupported("cos")   }
+    fn  cosh(self)                    -> Result<Decimal<SCALE, R>> { unsupported("cosh")  }
+    fn   exp(self)                    -> Result<Decimal<SCALE, R>> { unsupported("exp")   }
+    fn    ln(self)                    -> Result<Decimal<SCALE, R>> { unsupported("ln")    }
+    fn   log(self)                    -> Result<Decimal<SCALE, R>> { unsupported("log")   }
+    fn log10(self)                    -> Result<Decimal<SCALE, R>> { unsupported("log10") }
+    fn   sin(self)                    -> Result<Decimal<SCALE, R>> { unsupported("sin")   }
+    fn  sinh(self)                    -> Result<Decimal<SCALE, R>> { unsupported("sinh")  }
+    fn  sqrt(self)                    -> Result<Decimal<SCALE, R>> { unsupported("sqrt")  }
+    fn   tan(self)                    -> Result<Decimal<SCALE, R>> { unsupported("tan")   }
+    fn  tanh(self)                    -> Result<Decimal<SCALE, R>> { unsupported("tanh")  }
+
+    fn compare(&self, rhs: &Decimal<SCALE, R>) -> Result<Option<Ordering>> {
+        Ok(Some(self.mantissa.cmp(&rhs.mantissa)))
+    }
+
+    fn to_i64(&self) -> Result<Option<i64>> {
+        let (q, r) = self.mantissa.divrem(&Decimal::<SCALE, R>::one()).unwrap();
+        Ok(if r.is_zero() { q.to_i64() } else { None })
+    }
+
+    fn isprime(self) -> Result<Decimal<SCALE, R>> {
+        // trial division would not finish for large values, Miller-Rabin is exact below 2^63
+        let one = Decimal::<SCALE, R>::one();
+        let n = integral(self, "isprime")?.mantissa.divrem(&one).unwrap().0;
+        match n.to_i64() {
+            Some(n) => Ok(Decimal::from_mantissa(one.mul(&BigInt::from_u64(number_theory::is_prime(n.max(0) as u64) as u64)))),
+            None => Err(TinyExprError::Other(String::from("argument of `isprime` is too large"))),
+        }
+    }
+
+    fn   re(self) -> Result<Decimal<SCALE, R>> { Ok(self) }
+    fn   im(self) -> Result<Decimal<SCALE, R>> { Ok(Decimal::default()) }
+    fn conj(self) -> Result<Decimal<SCALE, R>> { Ok(self) }
+}
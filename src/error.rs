@@ -13,6 +13,12 @@ pub type Result<T> = result::Result<T, TinyExprError>;
 pub enum TinyExprError {
     /// Parse error
     Parse(ParseFloatError),
+    /// Operation is not supported by the numeric type used for evaluation
+    Unsupported(String),
+    /// Division by zero in a numeric type which cannot represent infinity
+    DivisionByZero,
+    /// Invalid expression, with the byte offset where the problem was found
+    Syntax(usize, String),
     /// Any other kind of error
     Other(String)
 }
@@ -21,19 +27,15 @@ impl fmt::Display for TinyExprError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             TinyExprError::Parse(ref err) => err.fmt(f),
+            TinyExprError::Unsupported(ref op) => write!(f, "`{}` is not supported by this number type", op),
+            TinyExprError::DivisionByZero => write!(f, "division by zero"),
+            TinyExprError::Syntax(position, ref message) => write!(f, "{} at position {}", message, position),
             TinyExprError::Other(ref err) => err.fmt(f)
         }
     }
 }
 
-impl Error for TinyExprError {
-    fn description(&self) -> &str {
-        match *self {
-            TinyExprError::Parse(ref err) => err.description(),
-            TinyExprError::Other(ref err) => err
-        }
-    }
-}
+impl Error for TinyExprError {}
 
 impl From<String> for TinyExprError {
     fn from(err: String) -> TinyExprError {
@@ -0,0 +1,408 @@
+//! Interval arithmetic for evaluating guaranteed bounds of an expression.
+
+use error::{Result, TinyExprError};
+use number::Number;
+use std::f64::consts;
+use std::fmt;
+use std::str::FromStr;
+
+/// Closed interval `[lo, hi]` of `f64` values.
+///
+/// Evaluating an expression with `Interval` values for its variables yields an interval which
+/// is guaranteed to contain every value the expression can take for variables in those ranges.
+/// All bounds are rounded outwards: results of `+`, `-`, `*`, `/` and `sqrt` are widened only
+/// when the floating point result is inexact, other functions are widened by two ulps to cover
+/// the error of the platform math library. Arguments outside of a function's domain are clipped
+/// to it, an interval lying entirely outside of it evaluates to `[NaN, NaN]`, like `f64` does.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Interval {
+    pub lo: f64,
+    pub hi: f64,
+}
+
+impl Interval {
+    /// Creates the interval `[lo, hi]`.
+    pub fn new(lo: f64, hi: f64) -> Interval {
+        Interval { lo, hi }
+    }
+
+    /// Creates an interval containing only `v`.
+    pub fn point(v: f64) -> Interval {
+        Interval::new(v, v)
+    }
+
+    /// Returns `true` if `v` lies within the interval.
+    pub fn contains(&self, v: f64) -> bool {
+        self.lo <= v && v <= self.hi
+    }
+
+    fn entire() -> Interval {
+        Interval::new(f64::NEG_INFINITY, f64::INFINITY)
+    }
+
+    fn nan() -> Interval {
+        Interval::new(f64::NAN, f64::NAN)
+    }
+
+    fn is_nan(&self) -> bool {
+        self.lo.is_nan() || self.hi.is_nan()
+    }
+
+    // smallest interval enclosing a value known only up to the rounding of `v`
+    fn around(v: f64) -> Interval {
+        Interval::new(v.next_down(), v.next_up())
+    }
+
+    // intersection with the domain [lo, hi] of a function
+    fn clip(self, lo: f64, hi: f64) -> Option<Interval> {
+        let ret = Interval::new(self.lo.max(lo), self.hi.min(hi));
+        if ret.is_nan() || ret.lo > ret.hi { None } else { Some(ret) }
+    }
+
+    // applies an increasing function, widening the result by two ulps
+    fn increasing(self, f: fn(f64) -> f64) -> Interval {
+        Interval::new(f(self.lo).next_down().next_down(), f(self.hi).next_up().next_up())
+    }
+
+    // applies a decreasing function, widening the result by two ulps
+    fn decreasing(self, f: fn(f64) -> f64) -> Interval {
+        Interval::new(f(self.hi).next_down().next_down(), f(self.lo).next_up().next_up())
+    }
+
+    fn clamp(self, lo: f64, hi: f64) -> Interval {
+        Interval::new(self.lo.max(lo), self.hi.min(hi))
+    }
+
+    fn hull(self, rhs: Interval) -> Interval {
+        Interval::new(self.lo.min(rhs.lo), self.hi.max(rhs.hi))
+    }
+
+    // sin or cos, `peak` and `trough` being the offsets of its maxima and minima within a period
+    fn periodic(self, f: fn(f64) -> f64, peak: f64, trough: f64) -> Interval {
+        if self.is_nan() {
+            return Interval::nan();
+        }
+        if self.hi - self.lo >= 2.0 * consts::PI || self.lo.abs().max(self.hi.abs()) > 1e15 {
+            return Interval::new(-1.0, 1.0);
+        }
+
+        let (a, b) = (f(self.lo), f(self.hi));
+        let mut ret = Interval::new(a.min(b).next_down().next_down(), a.max(b).next_up().next_up());
+        if self.contains_period_point(peak) {
+            ret.hi = 1.0;
+        }
+        if self.contains_period_point(trough) {
+            ret.lo = -1.0;
+        }
+        ret.clamp(-1.0, 1.0)
+    }
+
+    // whether `offset + 2*k*pi` lies in the interval for some integer k; the check is done
+    // with a small tolerance, so points very close to the bounds are assumed to be inside
+    fn contains_period_point(&self, offset: f64) -> bool {
+        let tau = 2.0 * consts::PI;
+        let tol = 1e-9 * (1.0 + self.lo.abs().max(self.hi.abs()));
+        let k = ((self.lo - offset - tol) / tau).ceil();
+        offset + k * tau <= self.hi + tol
+    }
+}
+
+impl From<f64> for Interval {
+    fn from(v: f64) -> Interval {
+        Interval::point(v)
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}, {}]", self.lo, self.hi)
+    }
+}
+
+// bounds of a value computed as `v` whose exact value is `v + err`
+fn bounds(v: f64, err: f64) -> (f64, f64) {
+    if !v.is_finite() || err.is_nan() {
+        (v.next_down(), v.next_up())
+    } else if err < 0.0 {
+        (v.next_down(), v)
+    } else if err > 0.0 {
+        (v, v.next_up())
+    } else {
+        (v, v)
+    }
+}
+
+fn add_bounds(a: f64, b: f64) -> (f64, f64) {
+    // TwoSum gives the exact rounding error of the addition
+    let s = a + b;
+    let bb = s - a;
+    bounds(s, (a - (s - bb)) + (b - bb))
+}
+
+fn mul_bounds(a: f64, b: f64) -> (f64, f64) {
+    // zero times an unbounded endpoint is zero for intervals
+    if a == 0.0 || b == 0.0 {
+        return (0.0, 0.0);
+    }
+    let p = a * b;
+    bounds(p, a.mul_add(b, -p))
+}
+
+fn div_bounds(a: f64, b: f64) -> (f64, f64) {
+    if a == 0.0 {
+        return (0.0, 0.0);
+    }
+    let q = a / b;
+    if b.is_infinite() {
+        return if a.is_infinite() { (f64::NEG_INFINITY, f64::INFINITY) } else { (q, q) };
+    }
+    // a - q*b is exact, its sign together with the sign of b tells which way q was rounded
+    let r = (-q).mul_add(b, a);
+    bounds(q, if b < 0.0 { -r } else { r })
+}
+
+// hull of op applied to every combination of bounds
+fn corners(a: Interval, b: Interval, op: fn(f64, f64) -> (f64, f64)) -> Interval {
+    let all = [op(a.lo, b.lo), op(a.lo, b.hi), op(a.hi, b.lo), op(a.hi, b.hi)];
+    all.iter().fold(Interval::new(f64::INFINITY, f64::NEG_INFINITY), |acc, &(lo, hi)| Interval::new(acc.lo.min(lo), acc.hi.max(hi)))
+}
+
+impl Number for Interval {
+    /// Literals which are not exactly representable as `f64` are enclosed by the two nearest values.
+    fn parse(literal: &str) -> Result<Interval> {
+        let v = f64::from_str(literal)?;
+        let exact = literal.split('.').nth(1).is_none_or(|frac| frac.bytes().all(|c| c == b'0'));
+        if exact && v <= 9007199254740992.0 { Ok(Interval::point(v)) } else { Ok(Interval::around(v)) }
+    }
+
+    fn add(self, rhs: Interval) -> Result<Interval> {
+        Ok(Interval::new(add_bounds(self.lo, rhs.lo).0, add_bounds(self.hi, rhs.hi).1))
+    }
+
+    fn sub(self, rhs: Interval) -> Result<Interval> {
+        Number::add(self, Number::neg(rhs)?)
+    }
+
+    fn mul(self, rhs: Interval) -> Result<Interval> {
+        if self.is_nan() || rhs.is_nan() {
+            return Ok(Interval::nan());
+        }
+        Ok(corners(self, rhs, mul_bounds))
+    }
+
+    fn div(self, rhs: Interval) -> Result<Interval> {
+        if self.is_nan() || rhs.is_nan() {
+            return Ok(Interval::nan());
+        }
+        if rhs.lo == 0.0 && rhs.hi == 0.0 {
+            return Err(TinyExprError::DivisionByZero);
+        }
+        if self.lo == 0.0 && self.hi == 0.0 {
+            return Ok(Interval::point(0.0));
+        }
+        if rhs.lo > 0.0 || rhs.hi < 0.0 {
+            return Ok(corners(self, rhs, div_bounds));
+        }
+        // the divisor contains zero, only a half-bounded result is possible when it is an endpoint
+        // of the divisor and the dividend stays away from zero
+        if self.lo > 0.0 && rhs.lo == 0.0 {
+            Ok(Interval::new(div_bounds(self.lo, rhs.hi).0, f64::INFINITY))
+        } else if self.lo > 0.0 && rhs.hi == 0.0 {
+            Ok(Interval::new(f64::NEG_INFINITY, div_bounds(self.lo, rhs.lo).1))
+        } else if self.hi < 0.0 && rhs.lo == 0.0 {
+            Ok(Interval::new(f64::NEG_INFINITY, div_bounds(self.hi, rhs.hi).1))
+        } else if self.hi < 0.0 && rhs.hi == 0.0 {
+            Ok(Interval::new(div_bounds(self.hi, rhs.lo).0, f64::INFINITY))
+        } else {
+            Ok(Interval::entire())
+        }
+    }
+
+    fn fmod(self, rhs: Interval) -> Result<Interval> {
+        if self.is_nan() || rhs.is_nan() {
+            return Ok(Interval::nan());
+        }
+        // fmod is exact, so within a single period of a fixed divisor the bounds map directly
+        if rhs.lo == rhs.hi && rhs.lo != 0.0 {
+            let (a, b) = (self.lo % rhs.lo, self.hi % rhs.lo);
+            if (self.lo / rhs.lo).trunc() == (self.hi / rhs.lo).trunc() && a <= b && (self.lo >= 0.0 || self.hi <= 0.0) {
+                return Ok(Interval::new(a, b));
+            }
+        }
+        let m = rhs.lo.abs().max(rhs.hi.abs());
+        Ok(Interval::new(if self.lo < 0.0 { -m } else { 0.0 }, if self.hi > 0.0 { m } else { 0.0 }))
+    }
+
+    fn neg(self) -> Result<Interval> {
+        Ok(Interval::new(-self.hi, -self.lo))
+    }
+
+    fn pow(self, rhs: Interval) -> Result<Interval> {
+        if self.is_nan() || rhs.is_nan() {
+            return Ok(Interval::nan());
+        }
+
+        // integer powers are defined for negative bases as well
+        if rhs.lo == rhs.hi && rhs.lo.fract() == 0.0 && rhs.lo.abs() <= 1e9 {
+            let n = rhs.lo;
+            if n == 0.0 {
+                return Ok(Interval::point(1.0));
+            }
+            if n < 0.0 {
+                return Number::div(Interval::point(1.0), Number::pow(self, Interval::point(-n))?);
+            }
+            let (a, b) = (self.lo.powf(n), self.hi.powf(n));
+            let ret = if n % 2.0 != 0.0 || self.lo >= 0.0 {
+                Interval::new(a.next_down().next_down(), b.next_up().next_up())
+            } else if self.hi <= 0.0 {
+                Interval::new(b.next_down().next_down(), a.next_up().next_up())
+            } else {
+                Interval::new(0.0, a.max(b).next_up().next_up())
+            };
+            // widening must not push even powers or powers of non-negative bases below zero
+            return Ok(if n % 2.0 == 0.0 || self.lo >= 0.0 { ret.clamp(0.0, f64::INFINITY) } else { ret });
+        }
+
+        // otherwise the base is restricted to non-negative values, where x^y is monotonic in
+        // both arguments and its extremes are found at the corners
+        let base = match self.clip(0.0, f64::INFINITY) {
+            Some(base) => base,
+            None => return Ok(Interval::nan()),
+        };
+        let ret = corners(base, rhs, |a, b| { let v = a.powf(b); (v.next_down().next_down(), v.next_up().next_up()) });
+        Ok(ret.clamp(0.0, f64::INFINITY))
+    }
+
+    fn  e() -> Result<Interval> { Ok(Interval::around(consts::E))  }
+    fn pi() -> Result<Interval> { Ok(Interval::around(consts::PI)) }
+
+    fn abs(self) -> Result<Interval> {
+        Ok(if self.lo >= 0.0 {
+            self
+        } else if self.hi <= 0.0 {
+            Number::neg(self)?
+        } else {
+            Interval::new(0.0, self.hi.max(-self.lo))
+        })
+    }
+
+    fn acos(self) -> Result<Interval> {
+        Ok(self.clip(-1.0, 1.0).map_or(Interval::nan(), |x| x.decreasing(f64::acos).clamp(0.0, consts::PI.next_up())))
+    }
+
+    fn asin(self) -> Result<Interval> {
+        let half_pi = consts::FRAC_PI_2.next_up();
+        Ok(self.clip(-1.0, 1.0).map_or(Interval::nan(), |x| x.increasing(f64::asin).clamp(-half_pi, half_pi)))
+    }
+
+    fn atan(self) -> Result<Interval> {
+        let half_pi = consts::FRAC_PI_2.next_up();
+        Ok(self.increasing(f64::atan).clamp(-half_pi, half_pi))
+    }
+
+    fn atan2(self, rhs: Interval) -> Result<Interval> {
+        if self.is_nan() || rhs.is_nan() {
+            return Ok(Interval::nan());
+        }
+        let pi = consts::PI.next_up();
+        // the angle is discontinuous along the negative x axis
+        if rhs.lo <= 0.0 && self.contains(0.0) {
+            return Ok(Interval::new(-pi, pi));
+        }
+        // for a box not crossing the discontinuity, extreme angles are found at its corners
+        let ret = corners(self, rhs, |y, x| { let v = y.atan2(x); (v.next_down().next_down(), v.next_up().next_up()) });
+        Ok(ret.clamp(-pi, pi))
+    }
+
+    fn cbrt(self) -> Result<Interval> {
+        // the real cube root, unlike x^(1/3) which is only defined for x >= 0
+        Ok(self.increasing(f64::cbrt))
+    }
+
+    fn ceil(self) -> Result<Interval> {
+        Ok(Interval::new(self.lo.ceil(), self.hi.ceil()))
+    }
+
+    fn cos(self) -> Result<Interval> {
+        Ok(self.periodic(f64::cos, 0.0, consts::PI))
+    }
+
+    fn cosh(self) -> Result<Interval> {
+        Ok(if self.lo >= 0.0 {
+            self.increasing(f64::cosh)
+        } else if self.hi <= 0.0 {
+            self.decreasing(f64::cosh)
+        } else {
+            Interval::new(1.0, f64::cosh(self.hi.max(-self.lo)).next_up().next_up())
+        }.clamp(1.0, f64::INFINITY))
+    }
+
+    fn exp(self) -> Result<Interval> {
+        Ok(self.increasing(f64::exp).clamp(0.0, f64::INFINITY))
+    }
+
+    fn floor(self) -> Result<Interval> {
+        Ok(Interval::new(self.lo.floor(), self.hi.floor()))
+    }
+
+    fn ln(self) -> Result<Interval> {
+        Ok(self.clip(0.0, f64::INFINITY).map_or(Interval::nan(), |x| x.increasing(f64::ln)))
+    }
+
+    fn log(self) -> Result<Interval> {
+        Number::log10(self)
+    }
+
+    fn log10(self) -> Result<Interval> {
+        Ok(self.clip(0.0, f64::INFINITY).map_or(Interval::nan(), |x| x.increasing(f64::log10)))
+    }
+
+    fn sin(self) -> Result<Interval> {
+        Ok(self.periodic(f64::sin, consts::FRAC_PI_2, -consts::FRAC_PI_2))
+    }
+
+    fn sinh(self) -> Result<Interval> {
+        Ok(self.increasing(f64::sinh))
+    }
+
+    fn sqrt(self) -> Result<Interval> {
+        // sqrt is correctly rounded, r*r - x tells which way
+        let root = |x: f64| { let r = x.sqrt(); bounds(r, -r.mul_add(r, -x)) };
+        Ok(self.clip(0.0, f64::INFINITY).map_or(Interval::nan(), |x| Interval::new(root(x.lo).0, root(x.hi).1)))
+    }
+
+    fn tan(self) -> Result<Interval> {
+        if self.is_nan() {
+            return Ok(Interval::nan());
+        }
+        // tan is increasing between its poles at pi/2 + k*pi
+        if self.hi - self.lo >= consts::PI || self.contains_period_point(consts::FRAC_PI_2) || self.contains_period_point(-consts::FRAC_PI_2) {
+            return Ok(Interval::entire());
+        }
+        Ok(self.increasing(f64::tan))
+    }
+
+    fn tanh(self) -> Result<Interval> {
+        Ok(self.increasing(f64::tanh).clamp(-1.0, 1.0))
+    }
+
+    fn   re(self) -> Result<Interval> { Ok(self) }
+    fn   im(self) -> Result<Interval> { Ok(Interval::point(0.0)) }
+    fn conj(self) -> Result<Interval> { Ok(self) }
+
+    // monotone functions, exact at both bounds
+    fn round(self) -> Result<Interval> { Ok(Interval::new(self.lo.round(), self.hi.round())) }
+    fn trunc(self) -> Result<Interval> { Ok(Interval::new(self.lo.trunc(), self.hi.trunc())) }
+    fn  sign(self) -> Result<Interval> { Ok(Interval::new(Number::sign(self.lo)?, Number::sign(self.hi)?)) }
+
+    fn arg(self) -> Result<Interval> {
+        let pi = Interval::around(consts::PI);
+        Ok(if self.lo >= 0.0 {
+            Interval::point(0.0)
+        } else if self.hi < 0.0 {
+            pi
+        } else {
+            Interval::point(0.0).hull(pi)
+        })
+    }
+}
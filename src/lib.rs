@@ -3,6 +3,11 @@
 //! This is a work in progress port of [TinyExpr](https://github.com/codeplea/tinyexpr) to Rust.
 //!
 //! Current release only supports built-in system functions (trigonometry, algebraic operations, constants, etc.).
+//! Expressions can be evaluated in any type implementing the `Number` trait (`f64` and `f32` are provided).
+//! Evaluating in `Complex` gives complex results where real evaluation would yield NaN, e.g. `sqrt(-1)`,
+//! while `Rational` evaluates arithmetic exactly, e.g. `1/3*3` is exactly `1`. Evaluating a compiled
+//! expression with `Interval` variables gives guaranteed bounds of its result, and `Decimal` provides
+//! fixed-point arithmetic with a chosen scale and rounding mode for currency amounts.
 //! See the `tests` module for more examples.
 //!
 //!# Quick Start
@@ -21,10 +26,24 @@
 //!```
 #[macro_use]
 extern crate bitflags;
+mod bigint;
+pub mod complex;
+pub mod decimal;
+mod distributions;
 pub mod error;
+pub mod interval;
+pub mod number;
+mod number_theory;
+mod random;
+pub mod rational;
+mod special;
 use error::Result;
-use std::f64::consts;
-use std::str::FromStr;
+use std::cmp::Ordering;
+pub use complex::Complex;
+pub use decimal::Decimal;
+pub use interval::Interval;
+pub use number::Number;
+pub use rational::Rational;
 
 bitflags! {
     #[doc(hidden)]
@@ -48,6 +67,11 @@ bitflags! {
         const TE_CLOSURE6  = 22;
         const TE_CLOSURE7  = 23;
         const TE_FLAG_PURE = 32;
+        const TE_FLAG_VARIADIC = 64; // the arity is the minimal number of arguments
+        const TE_VARIADIC1 = Self::TE_FUNCTION1.bits | Self::TE_FLAG_VARIADIC.bits;
+        const TE_VARIADIC2 = Self::TE_FUNCTION2.bits | Self::TE_FLAG_VARIADIC.bits;
+        const TE_FLAG_OPTIONAL = 128; // the last argument can be left out
+        const TE_OPTIONAL2 = Self::TE_FUNCTION2.bits | Self::TE_FLAG_OPTIONAL.bits;
         const TOK_NULL     = 24;
         const TOK_ERROR    = 25;
         const TOK_END      = 26;
@@ -71,6 +95,16 @@ macro_rules! is_pure {
     ($x:expr) => (($x & ExprType::TE_FLAG_PURE).bits() != 0)
 }
 
+#[allow(unused_macros)]
+macro_rules! is_variadic {
+    ($x:expr) => (($x & ExprType::TE_FLAG_VARIADIC).bits() != 0)
+}
+
+#[allow(unused_macros)]
+macro_rules! is_optional {
+    ($x:expr) => (($x & ExprType::TE_FLAG_OPTIONAL).bits() != 0)
+}
+
 #[allow(unused_macros)]
 macro_rules! is_function {
     ($x:expr) => (($x & ExprType::TE_FUNCTION0).bits() != 0)
@@ -86,90 +120,347 @@ macro_rules! arity {
     ($x:expr) => (if($x & (ExprType::TE_FUNCTION0 | ExprType::TE_CLOSURE0)).bits() != 0 { $x.bits() & 0x00000007 } else { 0 })
 }
 
+/// Operation performed by a function node, resolved to a `Number` method at evaluation time.
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Dummy, Add, Sub, Mul, Div, Fmod, Neg, Comma, Bitand, Bitor, Bitxor, Bitnot, Shl, Shr, Percent,
+    Square, Lt, Le, Gt, Ge, Eq, Ne, Assign, Local, Call, Let, In, Binding,
+    Abs, Acos, Acosh, Arg, Asin, Asinh, Atan, Atan2, Atanh, Avg, Beta, Binomcdf, Binominv, Binompdf,
+    Cbrt, Ceil, Clamp, Conj, Cos, Cosh, Cot, Csc, Deg, E, Erf, Erfc, Exp, Expcdf, Expinv, Expm1, Exppdf,
+    Fac, Floor, Fract, Gamma, Gammainc, Gammaincc, Gcd, Hypot, I, Idiv, If, Im, Isprime, Lcm, Lgamma,
+    Ln, Log, Log10, Log1p, Log2, Logncdf, Logninv, Lognpdf, Max, Median, Min, Mod, Ncr, Normcdf,
+    Norminv, Normpdf, Npr, Pi, Poisscdf, Poissinv, Poisspdf, Pow, Powmod, Rad, Rand, Randint, Randn, Re,
+    Round, Roundto, Sec, Sign, Sin, Sinh, Sqrt, Stddev, Sum, Tan, Tanh, Tcdf, Tinv, Tpdf, Trunc
+}
+
 // todo: introduce a Function struct to accomodate different arg numbers and ret values?
-const FUNCTIONS: [&'static str; 21] = ["abs", "acos", "asin", "atan", "atan2", "ceil", "cos",
-                                       "cosh", "e", "exp", "floor", "ln", "log", "log10",
-                                       "pi", "pow", "sin", "sinh", "sqrt", "tan", "tanh" ];
-const FUNCTION_TYPES: [(fn(f64, f64) -> f64, ExprType); 21] = [ (abs,  ExprType::TE_FUNCTION1), (acos,  ExprType::TE_FUNCTION1), (asin, ExprType::TE_FUNCTION1),
-                                                                (atan, ExprType::TE_FUNCTION1), (atan2, ExprType::TE_FUNCTION2), (ceil, ExprType::TE_FUNCTION1),
-                                                                (cos,  ExprType::TE_FUNCTION1), (cosh,  ExprType::TE_FUNCTION1), (e,    ExprType::TE_FUNCTION0),
-                                                                (exp,  ExprType::TE_FUNCTION1), (floor, ExprType::TE_FUNCTION1), (ln,   ExprType::TE_FUNCTION1),
-                                                                (log,  ExprType::TE_FUNCTION1), (log10, ExprType::TE_FUNCTION1), (pi,   ExprType::TE_FUNCTION0),
-                                                                (pow,  ExprType::TE_FUNCTION2), (sin,   ExprType::TE_FUNCTION1), (sinh, ExprType::TE_FUNCTION1),
-                                                                (sqrt, ExprType::TE_FUNCTION1), (tan,   ExprType::TE_FUNCTION1), (tanh, ExprType::TE_FUNCTION1)];
-
-fn dummy(_: f64, _: f64) -> f64 { panic!("called dummy!") } // todo
-fn   add(a: f64, b: f64) -> f64 { a + b }
-fn   sub(a: f64, b: f64) -> f64 { a - b }
-fn   mul(a: f64, b: f64) -> f64 { a * b }
-fn   div(a: f64, b: f64) -> f64 { a / b }
-fn  fmod(a: f64, b: f64) -> f64 { a % b }
-fn   neg(a: f64, _: f64) -> f64 { -a }
-fn comma(_: f64, b: f64) -> f64 {  b }
-// todo: this is added so that it works with current fptr... - need more types! no extra unused params!
-fn   abs(a: f64, _: f64) -> f64 { a.abs()    }
-fn  acos(a: f64, _: f64) -> f64 { a.acos()   }
-fn  asin(a: f64, _: f64) -> f64 { a.asin()   }
-fn  atan(a: f64, _: f64) -> f64 { a.atan()   }
-fn atan2(a: f64, b: f64) -> f64 { a.atan2(b) }
-fn  ceil(a: f64, _: f64) -> f64 { a.ceil()   }
-fn   cos(a: f64, _: f64) -> f64 { a.cos()    }
-fn  cosh(a: f64, _: f64) -> f64 { a.cosh()   }
-fn     e(_: f64, _: f64) -> f64 { consts::E  }
-fn   exp(a: f64, _: f64) -> f64 { a.exp()    }
-fn floor(a: f64, _: f64) -> f64 { a.floor()  }
-fn    ln(a: f64, _: f64) -> f64 { a.ln()     }
-fn   log(a: f64, _: f64) -> f64 { a.log10()  } // todo ?
-fn log10(a: f64, _: f64) -> f64 { a.log10()  }
-fn    pi(_: f64, _: f64) -> f64 { consts::PI }
-fn   pow(a: f64, b: f64) -> f64 { a.powf(b)  }
-fn   sin(a: f64, _: f64) -> f64 { a.sin()    }
-fn  sinh(a: f64, _: f64) -> f64 { a.sinh()   }
-fn  sqrt(a: f64, _: f64) -> f64 { a.sqrt()   }
-fn   tan(a: f64, _: f64) -> f64 { a.tan()    }
-fn  tanh(a: f64, _: f64) -> f64 { a.tanh()   }
+const FUNCTIONS: [&str; 89] = ["abs", "acos", "acosh", "arg", "asin", "asinh", "atan", "atan2", "atanh", "avg",
+                               "beta", "binomcdf", "binominv", "binompdf", "cbrt", "ceil", "clamp", "conj", "cos", "cosh",
+                               "cot", "csc", "deg", "e", "erf", "erfc", "exp", "expcdf", "expinv", "expm1",
+                               "exppdf", "fac", "floor", "fract", "gamma", "gammainc", "gammaincc", "gcd", "hypot", "i",
+                               "idiv", "if", "im", "isprime", "lcm", "lgamma", "ln", "log", "log10", "log1p",
+                               "log2", "logncdf", "logninv", "lognpdf", "max", "median", "min", "mod", "ncr", "normcdf",
+                               "norminv", "normpdf", "npr", "pi", "poisscdf", "poissinv", "poisspdf", "pow", "powmod", "rad",
+                               "rand", "randint", "randn", "re", "round", "roundto", "sec", "sign", "sin", "sinh",
+                               "sqrt", "stddev", "sum", "tan", "tanh", "tcdf", "tinv", "tpdf", "trunc" ];
+const FUNCTION_TYPES: [(Function, ExprType); 89] = [ (Function::Abs, ExprType::TE_FUNCTION1), (Function::Acos, ExprType::TE_FUNCTION1), (Function::Acosh, ExprType::TE_FUNCTION1),
+                                                     (Function::Arg, ExprType::TE_FUNCTION1), (Function::Asin, ExprType::TE_FUNCTION1), (Function::Asinh, ExprType::TE_FUNCTION1),
+                                                     (Function::Atan, ExprType::TE_FUNCTION1), (Function::Atan2, ExprType::TE_FUNCTION2), (Function::Atanh, ExprType::TE_FUNCTION1),
+                                                     (Function::Avg, ExprType::TE_VARIADIC1), (Function::Beta, ExprType::TE_FUNCTION2), (Function::Binomcdf, ExprType::TE_FUNCTION3),
+                                                     (Function::Binominv, ExprType::TE_FUNCTION3), (Function::Binompdf, ExprType::TE_FUNCTION3), (Function::Cbrt, ExprType::TE_FUNCTION1),
+                                                     (Function::Ceil, ExprType::TE_FUNCTION1), (Function::Clamp, ExprType::TE_FUNCTION3), (Function::Conj, ExprType::TE_FUNCTION1),
+                                                     (Function::Cos, ExprType::TE_FUNCTION1), (Function::Cosh, ExprType::TE_FUNCTION1), (Function::Cot, ExprType::TE_FUNCTION1),
+                                                     (Function::Csc, ExprType::TE_FUNCTION1), (Function::Deg, ExprType::TE_FUNCTION1), (Function::E, ExprType::TE_FUNCTION0),
+                                                     (Function::Erf, ExprType::TE_FUNCTION1), (Function::Erfc, ExprType::TE_FUNCTION1), (Function::Exp, ExprType::TE_FUNCTION1),
+                                                     (Function::Expcdf, ExprType::TE_FUNCTION2), (Function::Expinv, ExprType::TE_FUNCTION2), (Function::Expm1, ExprType::TE_FUNCTION1),
+                                                     (Function::Exppdf, ExprType::TE_FUNCTION2), (Function::Fac, ExprType::TE_FUNCTION1), (Function::Floor, ExprType::TE_FUNCTION1),
+                                                     (Function::Fract, ExprType::TE_FUNCTION1), (Function::Gamma, ExprType::TE_FUNCTION1), (Function::Gammainc, ExprType::TE_FUNCTION2),
+                                                     (Function::Gammaincc, ExprType::TE_FUNCTION2), (Function::Gcd, ExprType::TE_FUNCTION2), (Function::Hypot, ExprType::TE_FUNCTION2),
+                                                     (Function::I, ExprType::TE_FUNCTION0), (Function::Idiv, ExprType::TE_FUNCTION2), (Function::If, ExprType::TE_FUNCTION3),
+                                                     (Function::Im, ExprType::TE_FUNCTION1), (Function::Isprime, ExprType::TE_FUNCTION1), (Function::Lcm, ExprType::TE_FUNCTION2),
+                                                     (Function::Lgamma, ExprType::TE_FUNCTION1), (Function::Ln, ExprType::TE_FUNCTION1), (Function::Log, ExprType::TE_FUNCTION1),
+                                                     (Function::Log10, ExprType::TE_FUNCTION1), (Function::Log1p, ExprType::TE_FUNCTION1), (Function::Log2, ExprType::TE_FUNCTION1),
+                                                     (Function::Logncdf, ExprType::TE_FUNCTION3), (Function::Logninv, ExprType::TE_FUNCTION3), (Function::Lognpdf, ExprType::TE_FUNCTION3),
+                                                     (Function::Max, ExprType::TE_VARIADIC1), (Function::Median, ExprType::TE_VARIADIC1), (Function::Min, ExprType::TE_VARIADIC1),
+                                                     (Function::Mod, ExprType::TE_FUNCTION2), (Function::Ncr, ExprType::TE_FUNCTION2), (Function::Normcdf, ExprType::TE_FUNCTION3),
+                                                     (Function::Norminv, ExprType::TE_FUNCTION3), (Function::Normpdf, ExprType::TE_FUNCTION3), (Function::Npr, ExprType::TE_FUNCTION2),
+                                                     (Function::Pi, ExprType::TE_FUNCTION0), (Function::Poisscdf, ExprType::TE_FUNCTION2), (Function::Poissinv, ExprType::TE_FUNCTION2),
+                                                     (Function::Poisspdf, ExprType::TE_FUNCTION2), (Function::Pow, ExprType::TE_FUNCTION2), (Function::Powmod, ExprType::TE_FUNCTION3),
+                                                     (Function::Rad, ExprType::TE_FUNCTION1), (Function::Rand, ExprType::TE_FUNCTION0), (Function::Randint, ExprType::TE_FUNCTION2),
+                                                     (Function::Randn, ExprType::TE_FUNCTION2), (Function::Re, ExprType::TE_FUNCTION1), (Function::Round, ExprType::TE_OPTIONAL2),
+                                                     (Function::Roundto, ExprType::TE_FUNCTION2), (Function::Sec, ExprType::TE_FUNCTION1), (Function::Sign, ExprType::TE_FUNCTION1),
+                                                     (Function::Sin, ExprType::TE_FUNCTION1), (Function::Sinh, ExprType::TE_FUNCTION1), (Function::Sqrt, ExprType::TE_FUNCTION1),
+                                                     (Function::Stddev, ExprType::TE_VARIADIC2), (Function::Sum, ExprType::TE_VARIADIC1), (Function::Tan, ExprType::TE_FUNCTION1),
+                                                     (Function::Tanh, ExprType::TE_FUNCTION1), (Function::Tcdf, ExprType::TE_FUNCTION2), (Function::Tinv, ExprType::TE_FUNCTION2),
+                                                     (Function::Tpdf, ExprType::TE_FUNCTION2), (Function::Trunc, ExprType::TE_FUNCTION1)];
 
-#[doc(hidden)]
+impl Function {
+    // random numbers must not be folded into constants by `optimize`
+    fn is_pure(self) -> bool {
+        !matches!(self, Function::Rand | Function::Randint | Function::Randn)
+    }
+
+    fn call<T: Number>(self, args: Vec<T>) -> Result<T> {
+        let a = |i: usize| args[i].clone();
+
+        match self {
+            Function::Dummy => panic!("called dummy!"), // todo
+            Function::Add   => T::add(a(0), a(1)),
+            Function::Sub   => T::sub(a(0), a(1)),
+            Function::Mul   => T::mul(a(0), a(1)),
+            Function::Div   => T::div(a(0), a(1)),
+            Function::Fmod  => T::fmod(a(0), a(1)),
+            Function::Neg   => T::neg(a(0)),
+            Function::Comma => Ok(a(1)),
+            Function::Bitand => T::bitand(a(0), a(1)),
+            Function::Bitor  => T::bitor(a(0), a(1)),
+            Function::Bitxor => T::bitxor(a(0), a(1)),
+            Function::Bitnot => T::bitnot(a(0)),
+            Function::Shl   => T::shl(a(0), a(1)),
+            Function::Shr   => T::shr(a(0), a(1)),
+            Function::Percent => T::div(a(0), T::parse("100")?),
+            Function::Square => T::pow(a(0), T::parse("2")?),
+            Function::Lt    => comparison(&a(0), &a(1), |o| o == Ordering::Less),
+            Function::Le    => comparison(&a(0), &a(1), |o| o != Ordering::Greater),
+            Function::Gt    => comparison(&a(0), &a(1), |o| o == Ordering::Greater),
+            Function::Ge    => comparison(&a(0), &a(1), |o| o != Ordering::Less),
+            Function::Eq    => comparison(&a(0), &a(1), |o| o == Ordering::Equal),
+            Function::Ne    => comparison(&a(0), &a(1), |o| o != Ordering::Equal),
+            Function::Assign | Function::Local => panic!("locals are handled by eval"),
+            Function::Call => panic!("user-defined functions are handled by eval"),
+            Function::Let | Function::In | Function::Binding => panic!("`let` bindings are handled by eval"),
+            Function::Abs   => T::abs(a(0)),
+            Function::Acos  => T::acos(a(0)),
+            Function::Acosh => T::acosh(a(0)),
+            Function::Arg   => T::arg(a(0)),
+            Function::Asin  => T::asin(a(0)),
+            Function::Asinh => T::asinh(a(0)),
+            Function::Atan  => T::atan(a(0)),
+            Function::Atan2 => T::atan2(a(0), a(1)),
+            Function::Atanh => T::atanh(a(0)),
+            Function::Avg   => average(&args),
+            Function::Beta  => T::beta(a(0), a(1)),
+            Function::Binomcdf => T::binomcdf(a(0), a(1), a(2)),
+            Function::Binominv => T::binominv(a(0), a(1), a(2)),
+            Function::Binompdf => T::binompdf(a(0), a(1), a(2)),
+            Function::Cbrt  => T::cbrt(a(0)),
+            Function::Ceil  => T::ceil(a(0)),
+            Function::Clamp => T::clamp(a(0), a(1), a(2)),
+            Function::Conj  => T::conj(a(0)),
+            Function::Cos   => T::cos(a(0)),
+            Function::Cosh  => T::cosh(a(0)),
+            Function::Cot   => T::cot(a(0)),
+            Function::Csc   => T::csc(a(0)),
+            Function::Deg   => T::deg(a(0)),
+            Function::E     => T::e(),
+            Function::Erf   => T::erf(a(0)),
+            Function::Erfc  => T::erfc(a(0)),
+            Function::Exp   => T::exp(a(0)),
+            Function::Expcdf => T::expcdf(a(0), a(1)),
+            Function::Expinv => T::expinv(a(0), a(1)),
+            Function::Expm1 => T::expm1(a(0)),
+            Function::Exppdf => T::exppdf(a(0), a(1)),
+            Function::Fac   => T::fac(a(0)),
+            Function::Floor => T::floor(a(0)),
+            Function::Fract => T::fract(a(0)),
+            Function::Gamma => T::gamma(a(0)),
+            Function::Gammainc  => T::gammainc(a(0), a(1)),
+            Function::Gammaincc => T::gammaincc(a(0), a(1)),
+            Function::Gcd   => T::gcd(a(0), a(1)),
+            Function::Hypot => T::hypot(a(0), a(1)),
+            Function::I     => T::i(),
+            Function::Idiv  => T::idiv(a(0), a(1)),
+            Function::If    => panic!("`if` is handled by eval"),
+            Function::Im    => T::im(a(0)),
+            Function::Isprime => T::isprime(a(0)),
+            Function::Lcm   => T::lcm(a(0), a(1)),
+            Function::Lgamma => T::lgamma(a(0)),
+            Function::Ln    => T::ln(a(0)),
+            Function::Log   => T::log(a(0)), // todo ?
+            Function::Log10 => T::log10(a(0)),
+            Function::Log1p => T::log1p(a(0)),
+            Function::Log2  => T::log2(a(0)),
+            Function::Logncdf => T::logncdf(a(0), a(1), a(2)),
+            Function::Logninv => T::logninv(a(0), a(1), a(2)),
+            Function::Lognpdf => T::lognpdf(a(0), a(1), a(2)),
+            Function::Max   => extreme(&args, Ordering::Greater),
+            Function::Median => median(&args),
+            Function::Min   => extreme(&args, Ordering::Less),
+            Function::Mod   => T::rem_euclid(a(0), a(1)),
+            Function::Ncr   => T::ncr(a(0), a(1)),
+            Function::Normcdf => T::normcdf(a(0), a(1), a(2)),
+            Function::Norminv => T::norminv(a(0), a(1), a(2)),
+            Function::Normpdf => T::normpdf(a(0), a(1), a(2)),
+            Function::Npr   => T::npr(a(0), a(1)),
+            Function::Pi    => T::pi(),
+            Function::Poisscdf => T::poisscdf(a(0), a(1)),
+            Function::Poissinv => T::poissinv(a(0), a(1)),
+            Function::Poisspdf => T::poisspdf(a(0), a(1)),
+            Function::Pow   => T::pow(a(0), a(1)),
+            Function::Powmod => T::powmod(a(0), a(1), a(2)),
+            Function::Rad   => T::rad(a(0)),
+            Function::Rand  => T::rand(),
+            Function::Randint => T::randint(a(0), a(1)),
+            Function::Randn => T::randn(a(0), a(1)),
+            Function::Re    => T::re(a(0)),
+            Function::Round if args.len() == 2 => T::round_digits(a(0), a(1)),
+            Function::Round => T::round(a(0)),
+            Function::Roundto => T::roundto(a(0), a(1)),
+            Function::Sec   => T::sec(a(0)),
+            Function::Sign  => T::sign(a(0)),
+            Function::Sin   => T::sin(a(0)),
+            Function::Sinh  => T::sinh(a(0)),
+            Function::Sqrt  => T::sqrt(a(0)),
+            Function::Stddev => stddev(&args),
+            Function::Sum   => sum(&args),
+            Function::Tan   => T::tan(a(0)),
+            Function::Tanh  => T::tanh(a(0)),
+            Function::Tcdf  => T::tcdf(a(0), a(1)),
+            Function::Tinv  => T::tinv(a(0), a(1)),
+            Function::Tpdf  => T::tpdf(a(0), a(1)),
+            Function::Trunc => T::trunc(a(0)),
+        }
+    }
+}
+
+fn sum<T: Number>(args: &[T]) -> Result<T> {
+    let mut ret = args[0].clone();
+    for x in &args[1..] {
+        ret = T::add(ret, x.clone())?;
+    }
+    Ok(ret)
+}
+
+fn average<T: Number>(args: &[T]) -> Result<T> {
+    T::div(sum(args)?, T::parse(&args.len().to_string())?)
+}
+
+// 1 when `a` and `b` are ordered as `holds` requires, otherwise 0; NaN is unordered, so only `!=`
+// holds for it
+fn comparison<T: Number>(a: &T, b: &T, holds: fn(Ordering) -> bool) -> Result<T> {
+    let truth = match a.compare(b)? {
+        Some(ordering) => holds(ordering),
+        None => holds(Ordering::Less) && holds(Ordering::Greater),
+    };
+    T::parse(if truth { "1" } else { "0" })
+}
+
+// sample standard deviation, dividing by n-1
+fn stddev<T: Number>(args: &[T]) -> Result<T> {
+    let mean = average(args)?;
+    let mut squares = Vec::with_capacity(args.len());
+    for x in args {
+        let d = T::sub(x.clone(), mean.clone())?;
+        squares.push(T::mul(d.clone(), d)?);
+    }
+    T::sqrt(T::div(sum(&squares)?, T::parse(&(args.len() - 1).to_string())?)?)
+}
+
+// the first argument which is not ordered with itself, i.e. NaN
+fn unordered<T: Number>(args: &[T]) -> Result<Option<T>> {
+    for x in args {
+        if x.compare(x)?.is_none() {
+            return Ok(Some(x.clone()));
+        }
+    }
+    Ok(None)
+}
+
+// smallest or largest argument, NaN if any of them is NaN
+fn extreme<T: Number>(args: &[T], wanted: Ordering) -> Result<T> {
+    if let Some(nan) = unordered(args)? {
+        return Ok(nan);
+    }
+    let mut ret = &args[0];
+    for x in &args[1..] {
+        if x.compare(ret)? == Some(wanted) {
+            ret = x;
+        }
+    }
+    Ok(ret.clone())
+}
+
+// middle argument, or the mean of the two middle ones for an even count
+fn median<T: Number>(args: &[T]) -> Result<T> {
+    if let Some(nan) = unordered(args)? {
+        return Ok(nan);
+    }
+    let mut sorted = args.to_vec();
+    // all arguments are known to be ordered and comparable at this point
+    sorted.sort_by(|x, y| x.compare(y).ok().flatten().unwrap_or(Ordering::Equal));
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 1 {
+        Ok(sorted.swap_remove(mid))
+    } else {
+        average(&sorted[mid - 1..mid + 1])
+    }
+}
+
+/// Compiled expression tree.
+///
+/// `compile` produces an `Expr` which keeps numeric literals as written, so the same tree
+/// can be bound to any `Number` type with `bind` and then evaluated with `eval`.
 #[derive(Debug)]
-pub struct Expr {
+pub struct Expr<T = String> {
     pub e_type: ExprType,
-    pub value:  f64,
-    pub bound:  i8, // todo: Variable?
-    pub function:   fn(f64, f64) -> f64,
-    pub parameters: Vec<Expr> // todo: should this be Option<>? Also, Expr&?
+    pub value:  T,
+    pub bound:  usize, // index of the variable value passed to eval
+    pub function:   Function,
+    pub parameters: Vec<Expr<T>>, // todo: should this be Option<>? Also, Expr&?
+    functions: Vec<Expr<T>>, // bodies of the user-defined functions, by index, kept by the root only
 }
 
-impl Expr {
-    fn new() -> Expr {
+impl<T: Default> Expr<T> {
+    fn new() -> Expr<T> {
         Expr {
             e_type: ExprType::TOK_NULL,
-            value:  0.0,
+            value:  T::default(),
             bound:  0,
-            function:   dummy,
-            parameters: Vec::<Expr>::new()
+            function:   Function::Dummy,
+            parameters: Vec::<Expr<T>>::new(),
+            functions:  Vec::<Expr<T>>::new(),
         }
     }
 }
 
-impl Clone for Expr {
-    fn clone(&self) -> Expr {
+impl<T: Clone> Clone for Expr<T> {
+    fn clone(&self) -> Expr<T> {
         Expr {
             e_type: self.e_type,
-            value:  self.value,
+            value:  self.value.clone(),
             bound:  self.bound,
             function:   self.function,
-            parameters: self.parameters.clone()
+            parameters: self.parameters.clone(),
+            functions:  self.functions.clone(),
         }
     }
 }
 
+impl Expr {
+    /// Converts the expression to the given numeric type, evaluating constant parts upfront.
+    pub fn bind<T: Number>(&self) -> Result<Expr<T>> {
+        let mut ret = self.convert()?;
+        optimize(&mut ret)?;
+        for f in &mut ret.functions {
+            optimize(f)?;
+        }
+        Ok(ret)
+    }
+
+    // the same tree with its literals parsed, folded by the caller once it is complete
+    fn convert<T: Number>(&self) -> Result<Expr<T>> {
+        let mut ret = Expr::<T>::new();
+        ret.e_type = self.e_type;
+        ret.bound = self.bound;
+        ret.function = self.function;
+        if self.e_type == ExprType::TE_CONSTANT {
+            ret.value = T::parse(&self.value)?;
+        }
+        for p in &self.parameters {
+            ret.parameters.push(p.convert()?);
+        }
+        for f in &self.functions {
+            ret.functions.push(f.convert()?);
+        }
+        Ok(ret)
+    }
+}
+
+impl<T: Number> Expr<T> {
+    /// Evaluates the expression. `values` holds the values of the variables passed to `compile`, in the same order.
+    pub fn eval(&self, values: &[T]) -> Result<T> {
+        eval(self, values, &mut Frame::new(), &mut Calls::new(&self.functions))
+    }
+}
+
 
 #[doc(hidden)]
 #[derive(Debug)]
 pub struct Variable {
     pub name:     String,
-    pub address:  i8, // todo: this will have to go - handle variables? (no void*)
-    pub function: fn(f64, f64) -> f64,
+    pub address:  usize, // todo: this will have to go - handle variables? (no void*)
+    pub function: Function,
     pub v_type:   ExprType,
     pub context:  Vec<Expr>,
 }
@@ -179,8 +470,8 @@ impl Variable {
         Variable {
             name:     String::from(name),
             address:  0,
-            function: dummy,
-            v_type:   v_type,
+            function: Function::Dummy,
+            v_type,
             context:  Vec::<Expr>::new(),
         }
     }
@@ -202,12 +493,21 @@ impl Clone for Variable {
 struct State {
     pub next:   String,
     pub s_type: ExprType,
+    pub previous: ExprType, // type of the token before the current one
     pub n_idx:  usize,
-    pub value:  f64,
-    pub bound:  i8,
-    pub function: fn(f64, f64) -> f64,
+    pub value:  String,
+    pub bound:  usize,
+    pub function: Function,
     pub context:  Vec<Expr>,
     pub lookup:   Vec<Variable>,
+    pub options:  Options,
+    pub start:    usize, // position of the current token
+    pub bars:     usize, // absolute value bars opened since the last parenthesis
+    pub error:    Option<(usize, String)>,
+    pub name:     Option<String>, // text of the current token if it is a name
+    pub locals:   Vec<String>, // names assigned in a script, by slot
+    pub lets:     usize, // enclosing `let` bindings
+    pub functions: Vec<Expr>, // bodies of the user-defined functions, by index
 }
 
 impl State {
@@ -215,12 +515,21 @@ impl State {
         State {
             next:   String::from(expression),
             s_type: ExprType::TOK_NULL,
+            previous: ExprType::TOK_NULL,
             n_idx:  0,
-            value:  0.0,
+            value:  String::new(),
             bound:  0,
-            function: mul,
+            function: Function::Mul,
             context:  Vec::<Expr>::new(),
-            lookup:   Vec::<Variable>::new()
+            lookup:   Vec::<Variable>::new(),
+            options:  Options::default(),
+            start:    0,
+            bars:     0,
+            error:    None,
+            name:     None,
+            locals:   Vec::<String>::new(),
+            lets:     0,
+            functions: Vec::<Expr>::new(),
         }
     }
 }
@@ -241,8 +550,15 @@ fn new_expr(e_type: ExprType, params: Option<Vec<Expr>>) -> Expr {
 
 fn find_lookup(s: &State, txt: &str) -> Option<Variable> {
     for var in &s.lookup {
-        if &(*var.name) == txt {
-            return Some((*var).clone());
+        if var.name == txt {
+            return Some(var.clone());
+        }
+    }
+    if s.options.case_insensitive {
+        for var in &s.lookup {
+            if var.name.eq_ignore_ascii_case(txt) {
+                return Some(var.clone());
+            }
         }
     }
     
@@ -251,99 +567,279 @@ fn find_lookup(s: &State, txt: &str) -> Option<Variable> {
 
 fn find_builtin(txt: &str) -> Option<Variable> {
     if let Ok(idx) = FUNCTIONS.binary_search(&txt) {
-        let mut v = Variable::new(txt, FUNCTION_TYPES[idx].1 | ExprType::TE_FLAG_PURE);
-        v.function = FUNCTION_TYPES[idx].0;
+        let (function, mut v_type) = FUNCTION_TYPES[idx];
+        if function.is_pure() {
+            v_type |= ExprType::TE_FLAG_PURE;
+        }
+        let mut v = Variable::new(txt, v_type);
+        v.function = function;
         return Some(v);
     }
 
     None
 }
 
+// fails parsing, keeping the first error found
+fn syntax_error(s: &mut State, position: usize, message: &str) {
+    if s.error.is_none() {
+        s.error = Some((position, String::from(message)));
+    }
+    s.s_type = ExprType::TOK_ERROR;
+}
+
+// the parts of the state changed by reading tokens
+struct Snapshot {
+    n_idx:    usize,
+    start:    usize,
+    s_type:   ExprType,
+    previous: ExprType,
+    function: Function,
+    value:    String,
+    bound:    usize,
+    context:  Vec<Expr>,
+    error:    Option<(usize, String)>,
+    name:     Option<String>,
+}
+
+fn snapshot(s: &State) -> Snapshot {
+    Snapshot {
+        n_idx:    s.n_idx,
+        start:    s.start,
+        s_type:   s.s_type,
+        previous: s.previous,
+        function: s.function,
+        value:    s.value.clone(),
+        bound:    s.bound,
+        context:  s.context.clone(),
+        error:    s.error.clone(),
+        name:     s.name.clone(),
+    }
+}
+
+fn restore(s: &mut State, snapshot: Snapshot) {
+    s.n_idx = snapshot.n_idx;
+    s.start = snapshot.start;
+    s.s_type = snapshot.s_type;
+    s.previous = snapshot.previous;
+    s.function = snapshot.function;
+    s.value = snapshot.value;
+    s.bound = snapshot.bound;
+    s.context = snapshot.context;
+    s.error = snapshot.error;
+    s.name = snapshot.name;
+}
+
+// applies `f` to the next token, leaving the state as it is
+fn peek<R, F: FnOnce(&State) -> R>(s: &mut State, f: F) -> Result<R> {
+    let saved = snapshot(s);
+    next_token(s)?;
+    let ret = f(s);
+    restore(s, saved);
+    Ok(ret)
+}
+
+// whether the token after the current character can begin an operand, leaving the state as it is
+fn operand_follows(s: &mut State) -> Result<bool> {
+    s.n_idx += 1;
+    let ret = peek(s, |s| starts_operand(s) || (s.s_type == ExprType::TOK_INFIX && s.function == Function::Bitnot));
+    s.n_idx -= 1;
+    ret
+}
+
+// turns a name found in the lookup or among the builtins into the current token
+fn name_token(s: &mut State, v: Variable) {
+    if v.function == Function::Call {
+        s.bound = v.address;
+    }
+    match type_mask!(v.v_type) {
+        ExprType::TE_VARIABLE => { s.s_type = ExprType::TOK_VARIABLE; s.bound = v.address; },
+        ExprType::TE_CLOSURE0 => s.context = v.context,
+        ExprType::TE_CLOSURE1 => s.context = v.context,
+        ExprType::TE_CLOSURE2 => s.context = v.context,
+        ExprType::TE_CLOSURE3 => s.context = v.context,
+        ExprType::TE_CLOSURE4 => s.context = v.context,
+        ExprType::TE_CLOSURE5 => s.context = v.context,
+        ExprType::TE_CLOSURE6 => s.context = v.context,
+        ExprType::TE_CLOSURE7 => s.context = v.context,
+        ExprType::TE_FUNCTION0 => { s.s_type = v.v_type; s.function = v.function; s.bound = v.address; },
+        ExprType::TE_FUNCTION1 => { s.s_type = v.v_type; s.function = v.function; },
+        ExprType::TE_FUNCTION2 => { s.s_type = v.v_type; s.function = v.function; },
+        ExprType::TE_FUNCTION3 => { s.s_type = v.v_type; s.function = v.function; },
+        ExprType::TE_FUNCTION4 => { s.s_type = v.v_type; s.function = v.function; },
+        ExprType::TE_FUNCTION5 => { s.s_type = v.v_type; s.function = v.function; },
+        ExprType::TE_FUNCTION6 => { s.s_type = v.v_type; s.function = v.function; },
+        ExprType::TE_FUNCTION7 => { s.s_type = v.v_type; s.function = v.function; },
+        _ => {}
+    }
+}
+
+// length in bytes of the longest prefix of `txt` whose characters all satisfy `pred`
+fn prefix_len<F: Fn(char) -> bool>(txt: &str, pred: F) -> usize {
+    txt.find(|c: char| !pred(c)).unwrap_or(txt.len())
+}
+
+// length in bytes of the name at the start of `txt`: letters, digits and underscores, with dots joining
+// the parts of a path such as `order.total`, which is looked up as a whole
+fn name_len(txt: &str) -> usize {
+    let part = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut len = prefix_len(txt, part);
+    while txt[len..].starts_with('.') && txt[len + 1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        len += 1 + prefix_len(&txt[len + 1..], part);
+    }
+    len
+}
+
+// name quoted as `[Gross Margin %]` or with backticks, in which a doubled closing `]` or backtick stands
+// for itself, and the length in bytes of the quoted name; `None` if it is not closed
+fn quoted_name(txt: &str) -> Option<(String, usize)> {
+    let close = if txt.starts_with('[') { ']' } else { '`' };
+    let mut name = String::new();
+    let mut chars = txt.char_indices().skip(1).peekable();
+    while let Some((idx, c)) = chars.next() {
+        if c != close {
+            name.push(c);
+        } else if chars.peek().map(|&(_, c)| c) == Some(close) {
+            name.push(close);
+            chars.next();
+        } else {
+            return Some((name, idx + 1));
+        }
+    }
+    None
+}
+
 fn next_token(s: &mut State) -> Result<String> {
+    s.previous = s.s_type;
     s.s_type = ExprType::TOK_NULL;
+    s.name = None;
     
     while s.s_type == ExprType::TOK_NULL {
-        if s.n_idx == s.next.len() {
-            s.s_type = ExprType::TOK_END;
-            break;
-        }
-
-        let next_char = s.next.as_bytes()[s.n_idx] as char;
+        s.start = s.n_idx;
+        // the expression is walked by characters, so symbols such as `π` or `×` are read whole
+        let next_char = match s.next[s.n_idx..].chars().next() {
+            Some(c) => c,
+            None => {
+                s.s_type = ExprType::TOK_END;
+                break;
+            }
+        };
         // try reading a number
-        if (next_char >= '0' && next_char <= '9') || next_char == '.' {
-            let mut num_str = String::new();
-            let mut c = next_char;
-
-            // extract the number part to separate string which we then convert to f64
-            while s.n_idx < s.next.len() && (c >= '0' && c <= '9') || c == '.' {
-                num_str.push(c);
-                s.n_idx += 1;
-                if s.n_idx < s.next.len() {
-                    c = s.next.as_bytes()[s.n_idx] as char;
-                }
+        if next_char.is_ascii_digit() || next_char == '.' {
+            // extract the number part to separate string which is converted once the numeric type is known
+            let len = prefix_len(&s.next[s.n_idx..], |c| c.is_ascii_digit() || c == '.');
+            let literal = &s.next[s.n_idx..s.n_idx + len];
+            // there is no exponent notation, `1e5` would otherwise read as `1` followed by `e5`
+            let rest = &s.next[s.n_idx + len..];
+            let exponent = rest.starts_with(['e', 'E']) && !rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '_');
+            if literal.matches('.').count() > 1 || literal == "." || exponent {
+                let position = s.start;
+                syntax_error(s, position, "malformed number");
+                break;
             }
-            s.value  = f64::from_str(&num_str)?;
+            s.value  = String::from(literal);
+            s.n_idx += len;
             s.s_type = ExprType::TOK_NUMBER;
         } else {
             // look for a variable or builting function call
-            if next_char >= 'a' && next_char <= 'z' {
-                let mut txt_str = String::new();
-                let mut c = next_char;
-
-                while s.n_idx < s.next.len() && (c >= 'a' && c <= 'z') || (c >= '0' && c <= '9') {
-                    txt_str.push(c);
-                    s.n_idx += 1;
-                    if s.n_idx < s.next.len() {
-                        c = s.next.as_bytes()[s.n_idx] as char;
+            let quoted = next_char == '[' || next_char == '`';
+            if next_char.is_ascii_alphabetic() || next_char == '_' || quoted {
+                let txt_str = if quoted {
+                    match quoted_name(&s.next[s.n_idx..]) {
+                        Some((name, len)) => { s.n_idx += len; name },
+                        None => {
+                            let open = s.n_idx;
+                            syntax_error(s, open, "quoted name is not closed");
+                            break;
+                        }
                     }
-                }
+                } else {
+                    let len = name_len(&s.next[s.n_idx..]);
+                    s.n_idx += len;
+                    String::from(&s.next[s.n_idx - len..s.n_idx])
+                };
+                // builtins are lowercase
+                let builtin = if s.options.case_insensitive { txt_str.to_ascii_lowercase() } else { txt_str.clone() };
 
-                let mut var = find_lookup(&s, &txt_str);
-                if let None = var {
-                   var = find_builtin(&txt_str);
+                let mut var = find_lookup(s, &txt_str);
+                if var.is_none() {
+                   var = find_builtin(&builtin);
                 }
+                s.name = Some(txt_str);
 
-                if let Some(v) = var {
-                    match type_mask!(v.v_type) {
-                        ExprType::TE_VARIABLE => { s.s_type = ExprType::TOK_VARIABLE; s.bound = v.address; },
-                        ExprType::TE_CLOSURE0 => s.context = v.context,
-                        ExprType::TE_CLOSURE1 => s.context = v.context,
-                        ExprType::TE_CLOSURE2 => s.context = v.context,
-                        ExprType::TE_CLOSURE3 => s.context = v.context,
-                        ExprType::TE_CLOSURE4 => s.context = v.context,
-                        ExprType::TE_CLOSURE5 => s.context = v.context,
-                        ExprType::TE_CLOSURE6 => s.context = v.context,
-                        ExprType::TE_CLOSURE7 => s.context = v.context,
-                        ExprType::TE_FUNCTION0 => { s.s_type = v.v_type; s.function = v.function; },
-                        ExprType::TE_FUNCTION1 => { s.s_type = v.v_type; s.function = v.function; },
-                        ExprType::TE_FUNCTION2 => { s.s_type = v.v_type; s.function = v.function; },
-                        ExprType::TE_FUNCTION3 => { s.s_type = v.v_type; s.function = v.function; },
-                        ExprType::TE_FUNCTION4 => { s.s_type = v.v_type; s.function = v.function; },
-                        ExprType::TE_FUNCTION5 => { s.s_type = v.v_type; s.function = v.function; },
-                        ExprType::TE_FUNCTION6 => { s.s_type = v.v_type; s.function = v.function; },
-                        ExprType::TE_FUNCTION7 => { s.s_type = v.v_type; s.function = v.function; },
-                        _ => {}
-                    }
+                // `xor`, `let` and `in` are keywords spelled like names, unless quoted
+                if !quoted && (builtin == "xor" || builtin == "let" || builtin == "in") {
+                    s.s_type = ExprType::TOK_INFIX;
+                    s.function = match builtin.as_str() {
+                        "xor" => Function::Bitxor,
+                        "let" => Function::Let,
+                        _ => Function::In,
+                    };
+                } else if let Some(v) = var {
+                    name_token(s, v);
                 }
                 else {
                     s.s_type = ExprType::TOK_ERROR;
                 }
+            } else if next_char == '#' {
+                // a comment up to the end of the line
+                s.n_idx += prefix_len(&s.next[s.n_idx..], |c| c != '\n');
+            } else if s.next[s.n_idx..].starts_with("/*") {
+                // a comment up to the next `*/`, without nesting
+                match s.next[s.n_idx + 2..].find("*/") {
+                    Some(len) => s.n_idx += len + 4,
+                    None => {
+                        let open = s.n_idx;
+                        syntax_error(s, open, "comment is not closed");
+                    }
+                }
             } else {
                 // look for an operator or special character
-                match s.next.as_bytes()[s.n_idx] as char {
-                    '+' => { s.s_type = ExprType::TOK_INFIX; s.function = add; },
-                    '-' => { s.s_type = ExprType::TOK_INFIX; s.function = sub; },
-                    '*' => { s.s_type = ExprType::TOK_INFIX; s.function = mul; },
-                    '/' => { s.s_type = ExprType::TOK_INFIX; s.function = div; },
-                    '^' => { s.s_type = ExprType::TOK_INFIX; s.function = pow; },
-                    '%' => { s.s_type = ExprType::TOK_INFIX; s.function = fmod; },
+                let following = s.next[s.n_idx + next_char.len_utf8()..].chars().next();
+                match next_char {
+                    '+' => { s.s_type = ExprType::TOK_INFIX; s.function = Function::Add; },
+                    '-' | '−' => { s.s_type = ExprType::TOK_INFIX; s.function = Function::Sub; },
+                    '*' | '×' => { s.s_type = ExprType::TOK_INFIX; s.function = Function::Mul; },
+                    '/' | '÷' => { s.s_type = ExprType::TOK_INFIX; s.function = Function::Div; },
+                    '^' => { s.s_type = ExprType::TOK_INFIX; s.function = Function::Pow; },
+                    '²' => { s.s_type = ExprType::TOK_INFIX; s.function = Function::Square; },
+                    '%' => {
+                        s.s_type = ExprType::TOK_INFIX;
+                        s.function = if s.options.percent == Percent::Hundredth && !operand_follows(s)? { Function::Percent } else { Function::Fmod };
+                    },
+                    '!' if following == Some('=') => { s.s_type = ExprType::TOK_INFIX; s.function = Function::Ne; s.n_idx += 1; },
+                    '!' => { s.s_type = ExprType::TOK_INFIX; s.function = Function::Fac; },
+                    '&' => { s.s_type = ExprType::TOK_INFIX; s.function = Function::Bitand; },
+                    '|' => { s.s_type = ExprType::TOK_INFIX; s.function = Function::Bitor; },
+                    '~' => { s.s_type = ExprType::TOK_INFIX; s.function = Function::Bitnot; },
+                    '<' | '>' => {
+                        // `<<`, `>>`, `<=`, `>=` or a single `<` or `>`
+                        let less = next_char == '<';
+                        s.s_type = ExprType::TOK_INFIX;
+                        s.function = match following {
+                            Some(c) if c == next_char => if less { Function::Shl } else { Function::Shr },
+                            Some('=') => if less { Function::Le } else { Function::Ge },
+                            _ => if less { Function::Lt } else { Function::Gt },
+                        };
+                        if following == Some(next_char) || following == Some('=') {
+                            s.n_idx += 1;
+                        }
+                    },
+                    '=' if following == Some('=') => { s.s_type = ExprType::TOK_INFIX; s.function = Function::Eq; s.n_idx += 1; },
+                    '=' => { s.s_type = ExprType::TOK_INFIX; s.function = Function::Assign; },
+                    // `;` separates the statements of a script, only its last value is kept like with `,`
+                    ';' => { s.s_type = ExprType::TOK_INFIX; s.function = Function::Comma; },
+                    '≤' => { s.s_type = ExprType::TOK_INFIX; s.function = Function::Le; },
+                    '≥' => { s.s_type = ExprType::TOK_INFIX; s.function = Function::Ge; },
+                    '≠' => { s.s_type = ExprType::TOK_INFIX; s.function = Function::Ne; },
+                    'π' => name_token(s, find_builtin("pi").unwrap()),
+                    '√' => name_token(s, find_builtin("sqrt").unwrap()),
                     '(' =>  s.s_type = ExprType::TOK_OPEN,
                     ')' =>  s.s_type = ExprType::TOK_CLOSE,
                     ',' =>  s.s_type = ExprType::TOK_SEP,
-                    ' ' | '\t' | '\n' |'\r' => {},
+                    c if c.is_whitespace() => {},
                       _ => s.s_type = ExprType::TOK_ERROR
                 }
-                s.n_idx += 1;
+                s.n_idx += next_char.len_utf8();
             }
         }
     }
@@ -351,13 +847,79 @@ fn next_token(s: &mut State) -> Result<String> {
     Ok(String::new())
 }
 
+// whether the current token is `xor`, `let` or `in`, which are spelled like names
+fn is_keyword(s: &State) -> bool {
+    s.name.is_some() && s.s_type == ExprType::TOK_INFIX
+}
+
+// index of the user-defined function named by the current token in the function table
+fn callee(s: &State) -> usize {
+    if s.function == Function::Call { s.bound } else { 0 }
+}
+
+// `let a = value, b = value in body`, with the state at `let`; each name is visible in the values after
+// it and in the body, which extends as far as possible, hiding any other use of the name
+fn let_in(s: &mut State) -> Result<Expr> {
+    next_token(s)?;
+    if is_keyword(s) {
+        let position = s.start;
+        let message = format!("`{}` is a keyword", s.name.take().unwrap_or_default());
+        syntax_error(s, position, &message);
+        return Ok(new_expr(ExprType::TE_CONSTANT, None));
+    }
+    let name = match s.name.take() {
+        Some(name) => name,
+        None => {
+            let position = s.start;
+            syntax_error(s, position, "expected a name after `let`");
+            return Ok(new_expr(ExprType::TE_CONSTANT, None));
+        }
+    };
+    next_token(s)?;
+    if s.s_type != ExprType::TOK_INFIX || s.function != Function::Assign {
+        s.s_type = ExprType::TOK_ERROR;
+        return Ok(new_expr(ExprType::TE_CONSTANT, None));
+    }
+    next_token(s)?;
+    let value = expr(s)?;
+
+    let mut var = Variable::new(&name, ExprType::TE_FUNCTION0);
+    var.function = Function::Binding;
+    var.address = s.lets;
+    s.lookup.insert(0, var);
+    s.lets += 1;
+    let body = if s.s_type == ExprType::TOK_SEP {
+        // the next binding
+        Ok(let_in(s)?)
+    } else if s.s_type == ExprType::TOK_INFIX && s.function == Function::In {
+        next_token(s)?;
+        expr(s)
+    } else {
+        if s.s_type != ExprType::TOK_ERROR {
+            let position = s.start;
+            syntax_error(s, position, "expected `in` or `,` after a `let` binding");
+        }
+        Ok(new_expr(ExprType::TE_CONSTANT, None))
+    };
+    s.lets -= 1;
+    s.lookup.remove(0);
+
+    let mut ret = new_expr(ExprType::TE_FUNCTION2, Some(vec![value, body?]));
+    ret.function = Function::Let;
+    Ok(ret)
+}
+
 fn base(s: &mut State) -> Result<Expr> {
     let mut ret: Expr;
+    // only a call built here is converted, a parenthesized subexpression already is
+    let call = matches!(type_mask!(s.s_type),
+        ExprType::TE_FUNCTION0 | ExprType::TE_FUNCTION1 | ExprType::TE_FUNCTION2 | ExprType::TE_FUNCTION3 |
+        ExprType::TE_FUNCTION4 | ExprType::TE_FUNCTION5 | ExprType::TE_FUNCTION6 | ExprType::TE_FUNCTION7);
 
     match type_mask!(s.s_type) {
         ExprType::TOK_NUMBER => {
             ret = new_expr(ExprType::TE_CONSTANT, None);
-            ret.value = s.value;
+            ret.value = s.value.clone();
             next_token(s)?;
         },
         ExprType::TOK_VARIABLE => {
@@ -368,45 +930,57 @@ fn base(s: &mut State) -> Result<Expr> {
         ExprType::TE_FUNCTION0 | ExprType::TE_CLOSURE0 => {
             ret = new_expr(s.s_type, None);
             ret.function = s.function;
+            ret.bound = s.bound;
             // todo: set parameters
             /*if is_closure!(s.s_type) {
                 ret.parameters[0] = s.context[0].clone();
             }*/
             next_token(s)?;
+            // empty parentheses are optional, as in `rand()`; others are left for implicit multiplication
+            if s.s_type == ExprType::TOK_OPEN && !matches!(ret.function, Function::Local | Function::Binding)
+                && s.next[s.n_idx..].trim_start().starts_with(')') {
+                next_token(s)?;
+                next_token(s)?;
+            }
             // todo: set parameters
         },
-        ExprType::TE_FUNCTION1 | ExprType::TE_CLOSURE1 => {
+        ExprType::TE_FUNCTION1 | ExprType::TE_CLOSURE1 if !is_variadic!(s.s_type) => {
             ret = new_expr(s.s_type, None);
             ret.function = s.function;
-            // todo: set parameters
+            ret.bound = callee(s);
             next_token(s)?;
             ret.parameters.push(power(s)?);
-            // todo: set parameters
         },
+        ExprType::TE_FUNCTION1 | ExprType::TE_CLOSURE1  |
         ExprType::TE_FUNCTION2 | ExprType::TE_CLOSURE2  | ExprType::TE_FUNCTION3 |
         ExprType::TE_CLOSURE3  | ExprType::TE_FUNCTION4 | ExprType::TE_CLOSURE4  |
         ExprType::TE_FUNCTION5 | ExprType::TE_CLOSURE5  | ExprType::TE_FUNCTION6 |
         ExprType::TE_CLOSURE6  | ExprType::TE_FUNCTION7 | ExprType::TE_CLOSURE7 => {
-            let arity = arity!(s.s_type);
+            // variadic functions take at least `arity` arguments, the others exactly `arity`,
+            // or one less when their last argument is optional
+            let arity = arity!(s.s_type) as usize;
+            let max_args = if is_variadic!(s.s_type) { usize::MAX } else { arity };
+            let min_args = if is_optional!(s.s_type) { arity - 1 } else { arity };
 
             ret = new_expr(s.s_type, None);
             ret.function = s.function;
-            // todo: set parameters
+            ret.bound = callee(s);
             next_token(s)?;
 
             if s.s_type != ExprType::TOK_OPEN {
                 s.s_type = ExprType::TOK_ERROR;
             } else {
-                let mut idx = 0;
-                for _ in 0..arity {
+                let bars = s.bars;
+                s.bars = 0;
+                while ret.parameters.len() < max_args {
                     next_token(s)?;
                     ret.parameters.push(expr(s)?);
                     if s.s_type != ExprType::TOK_SEP {
                         break;
                     }
-                    idx += 1;
                 }
-                if s.s_type != ExprType::TOK_CLOSE || (idx != arity-1) {
+                s.bars = bars;
+                if s.s_type != ExprType::TOK_CLOSE || ret.parameters.len() < min_args {
                     s.s_type = ExprType::TOK_ERROR;
                 } else {
                     next_token(s)?;
@@ -414,40 +988,108 @@ fn base(s: &mut State) -> Result<Expr> {
             }
         },
         ExprType::TOK_OPEN => {
+            let bars = s.bars;
+            s.bars = 0;
             next_token(s)?;
             ret = list(s)?;
+            s.bars = bars;
             if s.s_type != ExprType::TOK_CLOSE {
                 s.s_type = ExprType::TOK_ERROR;
             } else {
                 next_token(s)?;
             }
         }
+        _ if s.s_type == ExprType::TOK_INFIX && s.function == Function::Let => {
+            ret = let_in(s)?;
+        },
+        _ if s.s_type == ExprType::TOK_INFIX && s.function == Function::Bitor => {
+            // a bar where an operand is expected opens an absolute value, the next bar after
+            // an operand closes it, so `|` as bitwise or needs parentheses within the bars
+            let open = s.start;
+            s.bars += 1;
+            next_token(s)?;
+            ret = new_expr(ExprType::TE_FUNCTION1 | ExprType::TE_FLAG_PURE, Some(vec![expr(s)?]));
+            ret.function = Function::Abs;
+            s.bars -= 1;
+            if s.s_type == ExprType::TOK_INFIX && s.function == Function::Bitor {
+                let close = s.start;
+                next_token(s)?;
+                // an operand right after the closing bar means a bar was meant to open another value
+                if !s.options.implicit_multiplication && starts_operand(s) {
+                    syntax_error(s, close, "ambiguous `|`");
+                }
+            } else if s.s_type != ExprType::TOK_ERROR {
+                syntax_error(s, open, "`|` is not closed");
+            }
+        }
         _ => {
             // todo: better error? Use NaN?
             ret = new_expr(ExprType::TE_VARIABLE, None);
             s.s_type = ExprType::TOK_ERROR;
-            ret.value = 0.0;
         }
     }
 
+    if call && s.options.angle_unit == AngleUnit::Degrees {
+        ret = in_degrees(ret);
+    }
+
     Ok(ret)
 }
 
+// converts the argument of trigonometric functions from degrees and the result of their inverses to degrees
+fn in_degrees(mut n: Expr) -> Expr {
+    let convert = |function: Function, param: Expr| {
+        let mut ret = new_expr(ExprType::TE_FUNCTION1 | ExprType::TE_FLAG_PURE, Some(vec![param]));
+        ret.function = function;
+        ret
+    };
+
+    match n.function {
+        Function::Sin | Function::Cos | Function::Tan | Function::Sec | Function::Csc | Function::Cot => {
+            let param = n.parameters.remove(0);
+            n.parameters.push(convert(Function::Rad, param));
+            n
+        },
+        Function::Asin | Function::Acos | Function::Atan | Function::Atan2 => convert(Function::Deg, n),
+        _ => n
+    }
+}
+
 fn power(s: &mut State) -> Result<Expr> {
     let mut sign = 1;
 
-    while s.s_type == ExprType::TOK_INFIX && (s.function == add || s.function == sub) {
-        if s.function == sub { sign = -sign; }
+    while s.s_type == ExprType::TOK_INFIX && (s.function == Function::Add || s.function == Function::Sub) {
+        if s.function == Function::Sub { sign = -sign; }
         next_token(s)?;
     }
 
     let mut ret: Expr;
 
-    if sign == 1 {
-        ret = base(s)?;
+    if s.s_type == ExprType::TOK_INFIX && s.function == Function::Bitnot {
+        next_token(s)?;
+        ret = new_expr(ExprType::TE_FUNCTION1 | ExprType::TE_FLAG_PURE, Some(vec![power(s)?]));
+        ret.function = Function::Bitnot;
     } else {
-        ret = new_expr(ExprType::TE_FUNCTION1 | ExprType::TE_FLAG_PURE, Some(vec![base(s)?.clone()]));
-        ret.function = neg;
+        ret = postfix(s)?;
+    }
+
+    if sign != 1 {
+        ret = new_expr(ExprType::TE_FUNCTION1 | ExprType::TE_FLAG_PURE, Some(vec![ret]));
+        ret.function = Function::Neg;
+    }
+
+    Ok(ret)
+}
+
+// factorial `!` and percent `%` after an operand
+fn postfix(s: &mut State) -> Result<Expr> {
+    let mut ret = base(s)?;
+
+    while s.s_type == ExprType::TOK_INFIX && matches!(s.function, Function::Fac | Function::Percent | Function::Square) {
+        let f = s.function;
+        next_token(s)?;
+        ret = new_expr(ExprType::TE_FUNCTION1 | ExprType::TE_FLAG_PURE, Some(vec![ret]));
+        ret.function = f;
     }
 
     Ok(ret)
@@ -458,7 +1100,7 @@ fn factor(s: &mut State) -> Result<Expr> {
     let mut ret = power(s)?;
 
     // todo: check functions here
-    while s.s_type == ExprType::TOK_INFIX && s.function == pow {
+    while s.s_type == ExprType::TOK_INFIX && s.function == Function::Pow {
         let f = s.function;
         next_token(s)?;
         ret = new_expr(ExprType::TE_FUNCTION2 | ExprType::TE_FLAG_PURE, Some(vec![ret.clone(), power(s)?.clone()]));
@@ -468,12 +1110,46 @@ fn factor(s: &mut State) -> Result<Expr> {
     Ok(ret)
 }
 
+// whether the current token can begin an operand, which is multiplied implicitly with the one before
+fn starts_operand(s: &State) -> bool {
+    // token types share their bits with function types, so these are listed one by one
+    matches!(type_mask!(s.s_type),
+        ExprType::TOK_NUMBER   | ExprType::TOK_VARIABLE | ExprType::TOK_OPEN     |
+        ExprType::TE_FUNCTION0 | ExprType::TE_FUNCTION1 | ExprType::TE_FUNCTION2 | ExprType::TE_FUNCTION3 |
+        ExprType::TE_FUNCTION4 | ExprType::TE_FUNCTION5 | ExprType::TE_FUNCTION6 | ExprType::TE_FUNCTION7 |
+        ExprType::TE_CLOSURE0  | ExprType::TE_CLOSURE1  | ExprType::TE_CLOSURE2  | ExprType::TE_CLOSURE3  |
+        ExprType::TE_CLOSURE4  | ExprType::TE_CLOSURE5  | ExprType::TE_CLOSURE6  | ExprType::TE_CLOSURE7)
+}
+
+// whether a `|` after an operand opens a closed absolute value rather than being bitwise or
+fn opens_bars(s: &mut State) -> Result<bool> {
+    if s.bars > 0 || s.s_type != ExprType::TOK_INFIX || s.function != Function::Bitor {
+        return Ok(false);
+    }
+    let saved = snapshot(s);
+    base(s)?;
+    let ret = s.s_type != ExprType::TOK_ERROR;
+    restore(s, saved);
+    Ok(ret)
+}
+
 fn term(s: &mut State) -> Result<Expr> {
     let mut ret = factor(s)?;
 
-    while s.s_type == ExprType::TOK_INFIX && (s.function == mul || s.function == div || s.function == fmod) {
-        let f = s.function;
-        next_token(s)?;
+    loop {
+        let f = if s.s_type == ExprType::TOK_INFIX && (s.function == Function::Mul || s.function == Function::Div || s.function == Function::Fmod) {
+            let f = s.function;
+            next_token(s)?;
+            f
+        } else if s.options.implicit_multiplication && s.s_type == ExprType::TOK_NUMBER && s.previous == ExprType::TOK_NUMBER {
+            // `2 3` or `1 000` is more likely a typo or a grouped number than a product
+            syntax_error(s, s.start, "missing operator between numbers");
+            break;
+        } else if s.options.implicit_multiplication && (starts_operand(s) || opens_bars(s)?) {
+            Function::Mul
+        } else {
+            break;
+        };
         ret = new_expr(ExprType::TE_FUNCTION2 | ExprType::TE_FLAG_PURE, Some(vec![ret.clone(), factor(s)?.clone()]));
         ret.function = f;
     }
@@ -481,10 +1157,10 @@ fn term(s: &mut State) -> Result<Expr> {
     Ok(ret)
 }
 
-fn expr(s: &mut State) -> Result<Expr> {
+fn arithmetic(s: &mut State) -> Result<Expr> {
     let mut ret = term(s)?;
 
-    while s.s_type == ExprType::TOK_INFIX && (s.function == add || s.function == sub) {
+    while s.s_type == ExprType::TOK_INFIX && (s.function == Function::Add || s.function == Function::Sub) {
         let f = s.function;
         next_token(s)?;
         ret = new_expr(ExprType::TE_FUNCTION2 | ExprType::TE_FLAG_PURE, Some(vec![ret.clone(), term(s)?.clone()]));
@@ -494,54 +1170,544 @@ fn expr(s: &mut State) -> Result<Expr> {
     Ok(ret)
 }
 
+// bitwise operators bind less tightly than arithmetic, in the order of C:
+// shifts, then `&`, then `xor`, then `|`
+fn shift(s: &mut State) -> Result<Expr> {
+    let mut ret = arithmetic(s)?;
+
+    while s.s_type == ExprType::TOK_INFIX && (s.function == Function::Shl || s.function == Function::Shr) {
+        let f = s.function;
+        next_token(s)?;
+        ret = new_expr(ExprType::TE_FUNCTION2 | ExprType::TE_FLAG_PURE, Some(vec![ret, arithmetic(s)?]));
+        ret.function = f;
+    }
+
+    Ok(ret)
+}
+
+fn bitwise_and(s: &mut State) -> Result<Expr> {
+    let mut ret = shift(s)?;
+
+    while s.s_type == ExprType::TOK_INFIX && s.function == Function::Bitand {
+        next_token(s)?;
+        ret = new_expr(ExprType::TE_FUNCTION2 | ExprType::TE_FLAG_PURE, Some(vec![ret, shift(s)?]));
+        ret.function = Function::Bitand;
+    }
+
+    Ok(ret)
+}
+
+fn bitwise_xor(s: &mut State) -> Result<Expr> {
+    let mut ret = bitwise_and(s)?;
+
+    while s.s_type == ExprType::TOK_INFIX && s.function == Function::Bitxor {
+        next_token(s)?;
+        ret = new_expr(ExprType::TE_FUNCTION2 | ExprType::TE_FLAG_PURE, Some(vec![ret, bitwise_and(s)?]));
+        ret.function = Function::Bitxor;
+    }
+
+    Ok(ret)
+}
+
+fn bitwise_or(s: &mut State) -> Result<Expr> {
+    let mut ret = bitwise_xor(s)?;
+
+    while s.s_type == ExprType::TOK_INFIX && s.function == Function::Bitor && s.bars == 0 {
+        let bar = s.start;
+        next_token(s)?;
+        // with implicit multiplication, a bar after an operand with nothing to or may have meant to open bars
+        if s.options.implicit_multiplication && matches!(s.s_type, ExprType::TOK_END | ExprType::TOK_CLOSE | ExprType::TOK_SEP) {
+            syntax_error(s, bar, "`|` is not closed");
+            return Ok(ret);
+        }
+        ret = new_expr(ExprType::TE_FUNCTION2 | ExprType::TE_FLAG_PURE, Some(vec![ret, bitwise_xor(s)?]));
+        ret.function = Function::Bitor;
+    }
+
+    Ok(ret)
+}
+
+fn expr(s: &mut State) -> Result<Expr> {
+    let mut ret = bitwise_or(s)?;
+
+    while s.s_type == ExprType::TOK_INFIX && matches!(s.function, Function::Lt | Function::Le | Function::Gt | Function::Ge | Function::Eq | Function::Ne) {
+        let f = s.function;
+        next_token(s)?;
+        ret = new_expr(ExprType::TE_FUNCTION2 | ExprType::TE_FLAG_PURE, Some(vec![ret, bitwise_or(s)?]));
+        ret.function = f;
+    }
+
+    Ok(ret)
+}
+
 fn list(s: &mut State) -> Result<Expr> {
     let mut ret = expr(s)?;
 
     while s.s_type == ExprType::TOK_SEP {
         next_token(s)?;
         ret = new_expr(ExprType::TE_FUNCTION2 | ExprType::TE_FLAG_PURE, Some(vec![ret.clone(), expr(s)?.clone()]));
-        ret.function = comma;
+        ret.function = Function::Comma;
     }
     
     Ok(ret)
 }
 
-fn optimize(n: &mut Expr) {
-    // evaluates as much as possible
-    if n.e_type == ExprType::TE_CONSTANT { return; }
-    if n.e_type == ExprType::TE_VARIABLE { return; }
+// parameters of the function definition `name(params) = body` starting at the current token with their
+// positions, leaving the state at its `=`; `None` for anything else, leaving the state as it is
+fn definition_header(s: &mut State) -> Result<Option<Vec<(String, usize)>>> {
+    let saved = snapshot(s);
+    let mut params = Vec::new();
+    next_token(s)?;
+    if s.s_type == ExprType::TOK_OPEN {
+        next_token(s)?;
+        while s.s_type != ExprType::TOK_CLOSE {
+            match s.name.take() {
+                Some(name) => params.push((name, s.start)),
+                None => break,
+            }
+            next_token(s)?;
+            if s.s_type == ExprType::TOK_SEP {
+                next_token(s)?;
+            } else if s.s_type != ExprType::TOK_CLOSE {
+                break;
+            }
+        }
+        if s.s_type == ExprType::TOK_CLOSE {
+            next_token(s)?;
+            if s.s_type == ExprType::TOK_INFIX && s.function == Function::Assign {
+                return Ok(Some(params));
+            }
+        }
+    }
+    restore(s, saved);
+    Ok(None)
+}
 
-    if (n.e_type & ExprType::TE_FLAG_PURE).bits() != 0  {
-        let mut _known = 1;
-        let arity = arity!(n.e_type);
-        
-        for _ in 0..arity {
-            // todo: optimize parameters
+// body of a function definition, with the state at its `=`; the body sees the parameters and the functions
+// defined before, including itself for recursion, and the function can be called from then on
+fn definition(s: &mut State, name: String, params: Vec<(String, usize)>, target: usize) -> Result<()> {
+    if params.len() > 7 {
+        syntax_error(s, target, "functions take at most 7 parameters");
+        return Ok(());
+    }
+    for (idx, (param, position)) in params.iter().enumerate() {
+        if params[..idx].iter().any(|(before, _)| before == param) {
+            syntax_error(s, *position, &format!("duplicate parameter `{}`", param));
+            return Ok(());
+        }
+    }
+    if let Some(var) = find_lookup(s, &name) {
+        if var.function != Function::Call {
+            syntax_error(s, target, "variables cannot be redefined as functions");
+            return Ok(());
         }
+    }
+    if find_builtin(&name.to_ascii_lowercase()).is_some() {
+        syntax_error(s, target, "builtin functions cannot be redefined");
+        return Ok(());
+    }
+    let f_type = ExprType::from_bits_truncate(ExprType::TE_FUNCTION0.bits() + params.len() as u64);
+
+    let mut lookup = Vec::<Variable>::new();
+    for (idx, (param, _)) in params.iter().enumerate() {
+        let mut var = Variable::new(param, ExprType::TE_VARIABLE);
+        var.address = idx;
+        lookup.push(var);
+    }
+    let index = s.functions.len();
+    let mut itself = Variable::new(&name, f_type);
+    itself.function = Function::Call;
+    itself.address = index;
+    lookup.push(itself);
+    lookup.extend(s.lookup.iter().filter(|v| v.function == Function::Call).cloned());
 
-        if _known != 0 {
-            n.value = eval(&n);
-            n.e_type = ExprType::TE_CONSTANT;
+    let outer = std::mem::replace(&mut s.lookup, lookup);
+    let lets = std::mem::replace(&mut s.lets, 0);
+    next_token(s)?;
+    let body = list(s)?;
+    // names of the script are not visible in the body
+    if s.s_type == ExprType::TOK_ERROR && s.error.is_none() {
+        if let Some(unknown) = s.name.clone() {
+            if outer.iter().any(|v| v.name == unknown && v.function != Function::Call) {
+                let position = s.start;
+                syntax_error(s, position, &format!("`{}` is not a parameter of `{}`", unknown, name));
+            }
         }
     }
+    s.lookup = outer;
+    s.lets = lets;
+
+    // calls refer to the body by its index, so it is stored once however often it is called
+    s.functions.push(body);
+    let mut var = Variable::new(&name, f_type);
+    var.function = Function::Call;
+    var.address = index;
+    s.lookup.retain(|v| v.name != name);
+    s.lookup.push(var);
+    Ok(())
 }
 
-fn compile(expression: &str, variables: Option<Vec<Variable>> ) -> Result<Option<Expr>> {
-    let mut s = State::new(expression);
-    if let Some(vars) = variables {
-        s.lookup = vars;
+// `name = value` or a function definition in a script, which gives no value, or a plain expression
+fn statement(s: &mut State) -> Result<Option<Expr>> {
+    let name = match s.name.clone() {
+        Some(name) => name,
+        None => return list(s).map(Some),
+    };
+    let target = s.start;
+    let keyword = is_keyword(s);
+    if let Some(params) = definition_header(s)? {
+        if keyword {
+            syntax_error(s, target, &format!("`{}` is a keyword", name));
+            return Ok(None);
+        }
+        definition(s, name, params, target)?;
+        return Ok(None);
+    }
+    if !peek(s, |s| s.s_type == ExprType::TOK_INFIX && s.function == Function::Assign)? {
+        return list(s).map(Some);
+    }
+    if keyword {
+        syntax_error(s, target, &format!("`{}` is a keyword", name));
+        return Ok(None);
+    }
+
+    let slot = match find_lookup(s, &name) {
+        Some(ref var) if var.function == Function::Local => Some(var.address),
+        Some(ref var) if var.v_type == ExprType::TE_VARIABLE => {
+            syntax_error(s, target, "variables passed to the script cannot be assigned");
+            return Ok(None);
+        },
+        Some(ref var) if var.function == Function::Call => {
+            syntax_error(s, target, "functions cannot be assigned");
+            return Ok(None);
+        },
+        _ if find_builtin(&name.to_ascii_lowercase()).is_some() => {
+            syntax_error(s, target, "builtin functions cannot be assigned");
+            return Ok(None);
+        },
+        _ => None,
+    };
+    next_token(s)?;
+    next_token(s)?;
+    // the name is visible from the next statement on
+    let value = list(s)?;
+    let slot = slot.unwrap_or_else(|| {
+        let mut var = Variable::new(&name, ExprType::TE_FUNCTION0);
+        var.function = Function::Local;
+        var.address = s.locals.len();
+        s.lookup.push(var);
+        s.locals.push(name);
+        s.locals.len() - 1
+    });
+
+    let mut ret = new_expr(ExprType::TE_FUNCTION1, Some(vec![value]));
+    ret.function = Function::Assign;
+    ret.bound = slot;
+    Ok(Some(ret))
+}
+
+// statements separated by `;`, with an optional `;` after the last one
+fn script(s: &mut State) -> Result<Expr> {
+    let mut ret = None;
+
+    loop {
+        if let Some(value) = statement(s)? {
+            ret = Some(match ret {
+                Some(before) => {
+                    let mut n = new_expr(ExprType::TE_FUNCTION2 | ExprType::TE_FLAG_PURE, Some(vec![before, value]));
+                    n.function = Function::Comma;
+                    n
+                },
+                None => value,
+            });
+        }
+        if s.s_type != ExprType::TOK_INFIX || s.function != Function::Comma {
+            break;
+        }
+        next_token(s)?;
+        if s.s_type == ExprType::TOK_END {
+            break;
+        }
+    }
+
+    match ret {
+        Some(ret) => Ok(ret),
+        None => {
+            if s.s_type == ExprType::TOK_END {
+                let end = s.start;
+                syntax_error(s, end, "a script needs a statement with a value");
+            }
+            Ok(new_expr(ExprType::TE_CONSTANT, None))
+        }
+    }
+}
+
+fn optimize<T: Number>(n: &mut Expr<T>) -> Result<()> {
+    // evaluates as much as possible
+    if n.e_type == ExprType::TE_CONSTANT { return Ok(()); }
+    if n.e_type == ExprType::TE_VARIABLE { return Ok(()); }
+
+    // a branch of `if` may fail where it is not taken, so only the one chosen by a known condition is folded
+    if n.function == Function::If {
+        optimize(&mut n.parameters[0])?;
+        if n.parameters[0].e_type == ExprType::TE_CONSTANT {
+            let branch = if is_zero(&n.parameters[0].value)? { 2 } else { 1 };
+            *n = n.parameters.swap_remove(branch);
+            optimize(n)?;
+        }
+        return Ok(());
+    }
+
+    // constant arguments of impure functions are still folded
+    let mut known = true;
+    for p in &mut n.parameters {
+        optimize(p)?;
+        if p.e_type != ExprType::TE_CONSTANT {
+            known = false;
+        }
+    }
+
+    if known && is_pure!(n.e_type) {
+        n.value = eval(n, &[], &mut Frame::new(), &mut Calls::new(&[]))?;
+        n.e_type = ExprType::TE_CONSTANT;
+        n.parameters.clear();
     }
 
-    arity!(s.s_type);
+    Ok(())
+}
+
+/// Unit of the angles taken by trigonometric functions and returned by their inverses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AngleUnit {
+    #[default]
+    Radians,
+    Degrees,
+}
+
+/// Meaning of a `%` sign which is not followed by an operand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Percent {
+    /// `%` is always the remainder operator, so `50%` is an error.
+    #[default]
+    Remainder,
+    /// `50%` divides by 100. A `%` followed by something which can begin an operand, like a number,
+    /// a name or `(`, is still the remainder: `7 % 3` is 1. Signs do not count, so `50% - 10` is `0.5 - 10`
+    /// and the remainder with a negative operand needs parentheses, `7 % (-3)`.
+    Hundredth,
+}
+
+/// Options changing how `compile_with` reads an expression.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Unit of `sin`, `cos`, `tan`, `sec`, `csc`, `cot` arguments and of `asin`, `acos`, `atan`, `atan2` results.
+    /// `deg` and `rad` convert between the units in either mode.
+    pub angle_unit: AngleUnit,
+    /// Multiplies adjacent operands without an operator in between, as in `2x`, `3(a+b)`, `(a+b)(a-b)` or `2pi`.
+    /// It has the precedence of `*`, so `1/2x` is `(1/2)*x` and `2x^2` is `2*(x^2)`. Names are never split,
+    /// `xy` is a single name. A function name followed by an operand still calls the function, whose argument
+    /// without parentheses is a single operand with its sign and powers: `sin x` is `sin(x)`, `2sin x cos x`
+    /// is `2*sin(x)*cos(x)` and `sin 2x` is `sin(2)*x`. A `|` after an operand multiplies by an absolute value
+    /// when a closing bar follows, so `2|x|` is `2*abs(x)`, while `a | b` is still bitwise or. Within bars such a
+    /// `|` closes them instead. Two numbers in a row, as in `2 3` or `1 000`, are a syntax error.
+    pub implicit_multiplication: bool,
+    /// Meaning of `%` without a right operand. The postfix factorial `5!` is always available.
+    pub percent: Percent,
+    /// Matches names regardless of ASCII case, so `SQRT(X)` calls `sqrt` with the variable `x`.
+    /// A variable spelled exactly as written is preferred over one differing in case.
+    pub case_insensitive: bool,
+    /// Function definitions such as `f(x, y) = x^2 + y^2`, which can be called from the expression and
+    /// from the definitions after them. A body only sees its parameters and the functions defined so far,
+    /// itself included, so recursion ends through `if(condition, then, else)`, which only evaluates the
+    /// chosen branch; calls nest at most `MAX_CALL_DEPTH` deep and their bodies at most `MAX_EVAL_DEPTH`. The condition
+    /// of `if` is compared with zero, so number types without comparisons such as `Interval` or `Complex` report
+    /// `if` as unsupported. Builtins cannot be redefined. Scripts can
+    /// define functions as well.
+    pub definitions: Vec<String>,
+}
+
+/// Compiles an expression for repeated evaluation. Names listed in `variables` can be used
+/// in the expression, their values are passed to `Expr::eval` in the same order.
+/// `let d = b^2 - 4*a*c in (-b + sqrt(d)) / (2*a)` names a value computed once per evaluation,
+/// hiding any variable of the same name in the body after `in`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate tinyexpr;
+///
+/// let expr = tinyexpr::compile("x*y+1", &["x", "y"]).unwrap().bind::<f64>().unwrap();
+///
+/// // "result" should contain a "7"
+/// let result = expr.eval(&[2.0, 3.0]).unwrap();
+/// ```
+pub fn compile(expression: &str, variables: &[&str]) -> Result<Expr> {
+    compile_with(expression, variables, &Options::default())
+}
+
+/// Same as `compile`, but reads the expression according to `options`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate tinyexpr;
+/// use tinyexpr::{AngleUnit, Options};
+///
+/// let options = Options { angle_unit: AngleUnit::Degrees, ..Options::default() };
+/// let expr = tinyexpr::compile_with("asin(x)", &["x"], &options).unwrap().bind::<f64>().unwrap();
+///
+/// // "result" should contain a "30", up to rounding
+/// let result = expr.eval(&[0.5]).unwrap();
+/// ```
+pub fn compile_with(expression: &str, variables: &[&str], options: &Options) -> Result<Expr> {
+    let mut s = new_state(expression, variables, options)?;
     next_token(&mut s)?;
     let mut root = list(&mut s)?;
+    finish(&s)?;
+
+    root.functions = s.functions;
+    Ok(root)
+}
+
+fn new_state(expression: &str, variables: &[&str], options: &Options) -> Result<State> {
+    let mut s = State::new(expression);
+    s.options = options.clone();
+    for (idx, name) in variables.iter().enumerate() {
+        let mut var = Variable::new(name, ExprType::TE_VARIABLE);
+        var.address = idx;
+        s.lookup.push(var);
+    }
+    let (functions, bodies) = define_all(options)?;
+    s.lookup.extend(functions);
+    s.functions = bodies;
+    Ok(s)
+}
+
+// functions of `options.definitions` and their bodies
+fn define_all(options: &Options) -> Result<(Vec<Variable>, Vec<Expr>)> {
+    let mut functions = Vec::new();
+    let mut bodies = Vec::new();
+    for (idx, text) in options.definitions.iter().enumerate() {
+        let mut d = State::new(text);
+        d.options = options.clone();
+        d.lookup = functions;
+        d.functions = bodies;
+        next_token(&mut d)?;
+        let target = d.start;
+        match (d.name.clone(), definition_header(&mut d)?) {
+            (Some(name), Some(params)) => {
+                definition(&mut d, name, params, target)?;
+            },
+            _ => syntax_error(&mut d, target, "expected a function definition"),
+        }
+        finish(&d).map_err(|err| match err {
+            error::TinyExprError::Syntax(position, message) => error::TinyExprError::Syntax(position, format!("{} in definition {}", message, idx + 1)),
+            err => err,
+        })?;
+        functions = d.lookup;
+        bodies = d.functions;
+    }
+    Ok((functions, bodies))
+}
 
+// fails unless the whole input was parsed
+fn finish(s: &State) -> Result<()> {
     if s.s_type != ExprType::TOK_END {
-        return Ok(None)
+        let (position, message) = match s.error {
+            Some(ref error) => error.clone(),
+            None if s.start == s.next.len() => (s.start, String::from("unexpected end of expression")),
+            None => (s.start, String::from("unexpected token")),
+        };
+        return Err(error::TinyExprError::Syntax(position, message))
+    }
+    Ok(())
+}
+
+/// Compiled script: statements separated by `;`, which may assign values to local names
+/// with `name = expression`. A local can be used by the statements after its assignment,
+/// and assigned again; the variables passed to `compile_script` cannot be assigned. A statement
+/// `f(x, y) = expression` defines a function, see `Options::definitions`.
+///
+/// Like `Expr`, a `Script` keeps numeric literals as written until `bind` is called.
+#[derive(Debug, Clone)]
+pub struct Script<T = String> {
+    body:      Expr<T>,
+    variables: usize,
+    locals:    Vec<String>,
+}
+
+/// Outcome of one evaluation of a `Script`.
+#[derive(Debug, Clone)]
+pub struct Evaluation<T> {
+    /// Value of the last statement.
+    pub value:  T,
+    /// Final value of each local, in the order of their first assignment.
+    pub locals: Vec<(String, T)>,
+}
+
+impl<T> Evaluation<T> {
+    /// Final value of the local `name`, if the script assigns it.
+    pub fn local(&self, name: &str) -> Option<&T> {
+        self.locals.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+}
+
+impl<T> Script<T> {
+    /// Names assigned by the script, in the order of their first assignment.
+    pub fn locals(&self) -> &[String] {
+        &self.locals
+    }
+}
+
+impl Script {
+    /// Converts the script to the given numeric type, evaluating constant parts upfront.
+    pub fn bind<T: Number>(&self) -> Result<Script<T>> {
+        Ok(Script { body: self.body.bind()?, variables: self.variables, locals: self.locals.clone() })
+    }
+}
+
+impl<T: Number> Script<T> {
+    /// Runs the script and returns the value of its last statement. `values` holds the values
+    /// of the variables passed to `compile_script`, in the same order.
+    pub fn eval(&self, values: &[T]) -> Result<T> {
+        Ok(self.run(values)?.value)
     }
 
-    optimize(&mut root);
-    Ok(Some(root))
+    /// Runs the script and returns the value of its last statement along with its locals.
+    pub fn run(&self, values: &[T]) -> Result<Evaluation<T>> {
+        if values.len() < self.variables {
+            return Err(error::TinyExprError::Other(format!("expected {} values, got {}", self.variables, values.len())));
+        }
+        let mut frame = Frame::new();
+        let value = eval(&self.body, &values[..self.variables], &mut frame, &mut Calls::new(&self.body.functions))?;
+        Ok(Evaluation { value, locals: self.locals.iter().cloned().zip(frame.locals).collect() })
+    }
+}
+
+/// Compiles a script of statements separated by `;`, see `Script`.
+///
+/// # Examples
+///
+/// ```
+/// extern crate tinyexpr;
+///
+/// let script = tinyexpr::compile_script("a = x*2; b = a+1; a*b", &["x"]).unwrap().bind::<f64>().unwrap();
+/// let result = script.run(&[3.0]).unwrap();
+/// assert_eq!(result.value, 42.0);
+/// assert_eq!(result.local("b"), Some(&7.0));
+/// ```
+pub fn compile_script(source: &str, variables: &[&str]) -> Result<Script> {
+    compile_script_with(source, variables, &Options::default())
+}
+
+/// Same as `compile_script`, but reads the script according to `options`.
+pub fn compile_script_with(source: &str, variables: &[&str], options: &Options) -> Result<Script> {
+    let mut s = new_state(source, variables, options)?;
+    next_token(&mut s)?;
+    let mut body = script(&mut s)?;
+    finish(&s)?;
+
+    body.functions = s.functions;
+    Ok(Script { body, variables: variables.len(), locals: s.locals })
 }
 
 /// Interprets a string expression as a mathematical expresion, evaluates it and returns its result.
@@ -555,28 +1721,170 @@ fn compile(expression: &str, variables: Option<Vec<Variable>> ) -> Result<Option
 /// let result = tinyexpr::interp("2+2").unwrap();
 /// ```
 pub fn interp(expression: &str) -> Result<f64> {
-    match compile(expression, None) {
-        Ok(Some(expr)) => Ok(eval(&expr)),
-        Err(e)         => Err(e),
-        _              => Err(error::TinyExprError::Other(String::from("NaN")))
+    interp_as::<f64>(expression)
+}
+
+/// Same as `interp`, but evaluates the expression using the given numeric type.
+///
+/// # Examples
+///
+/// ```
+/// extern crate tinyexpr;
+///
+/// // "result" should contain a "0.5" as f32
+/// let result = tinyexpr::interp_as::<f32>("1/2").unwrap();
+/// ```
+pub fn interp_as<T: Number>(expression: &str) -> Result<T> {
+    compile(expression, &[])?.bind::<T>()?.eval(&[])
+}
+
+/// Seeds the generator behind `rand`, `randint` and `randn` for the current thread, so that
+/// the same sequence of evaluations gives the same random numbers again. Without a seed every
+/// thread starts from a different random state.
+///
+/// # Examples
+///
+/// ```
+/// extern crate tinyexpr;
+///
+/// let expr = tinyexpr::compile("randint(1, 6)", &[]).unwrap().bind::<f64>().unwrap();
+/// tinyexpr::seed_random(42);
+/// let first = expr.eval(&[]).unwrap();
+/// tinyexpr::seed_random(42);
+/// assert_eq!(expr.eval(&[]).unwrap(), first);
+/// ```
+pub fn seed_random(seed: u64) {
+    random::seed(seed)
+}
+
+/// Maximum nesting of calls to user-defined functions, which stops endless recursion.
+pub const MAX_CALL_DEPTH: usize = 256;
+
+/// Maximum nesting of the nodes being evaluated, counted through calls to user-defined functions, so that
+/// deep recursion fails with an error instead of overflowing the stack. A thread with the default 2 MB stack
+/// has room for it, even in unoptimized builds.
+pub const MAX_EVAL_DEPTH: usize = 1000;
+
+// values named in a script or a function body while it is evaluated
+struct Frame<T> {
+    locals: Vec<T>, // assigned so far in a script, by slot
+    lets:   Vec<T>, // of the enclosing `let` bindings, the innermost last
+}
+
+impl<T> Frame<T> {
+    fn new() -> Frame<T> {
+        Frame { locals: Vec::new(), lets: Vec::new() }
     }
 }
 
-// todo
-fn eval(n: &Expr) -> f64 {
+// user-defined functions of the evaluated tree and how deep their calls are nested
+struct Calls<'a, T> {
+    functions: &'a [Expr<T>],
+    depth:     usize,
+    nesting:   usize, // of nodes being evaluated
+}
+
+impl<'a, T> Calls<'a, T> {
+    fn new(functions: &'a [Expr<T>]) -> Calls<'a, T> {
+        Calls { functions, depth: 0, nesting: 0 }
+    }
+}
+
+fn eval<T: Number>(n: &Expr<T>, values: &[T], frame: &mut Frame<T>, calls: &mut Calls<T>) -> Result<T> {
+    // each nested node takes stack space, within or without calls
+    if calls.nesting == MAX_EVAL_DEPTH {
+        return too_deep();
+    }
+    calls.nesting += 1;
+    let ret = eval_node(n, values, frame, calls);
+    calls.nesting -= 1;
+    ret
+}
+
+fn eval_node<T: Number>(n: &Expr<T>, values: &[T], frame: &mut Frame<T>, calls: &mut Calls<T>) -> Result<T> {
     match type_mask!(n.e_type) {
-        ExprType::TE_CONSTANT => n.value,
-        ExprType::TE_VARIABLE => n.bound as f64,
+        ExprType::TE_CONSTANT => Ok(n.value.clone()),
+        ExprType::TE_VARIABLE => variable(n, values),
         ExprType::TE_FUNCTION0 | ExprType::TE_FUNCTION1 | ExprType::TE_FUNCTION2 | ExprType::TE_FUNCTION3 |
         ExprType::TE_FUNCTION4 | ExprType::TE_FUNCTION5 | ExprType::TE_FUNCTION6 | ExprType::TE_FUNCTION7 => {
-            match arity!(n.e_type) {
-                // todo: REALLY need more function pointer types to avoid hacks like this 0.0 here...
-                0 => ((*n).function)(0.0, 0.0),
-                1 => ((*n).function)(eval(&n.parameters[0]), 0.0),
-                2 => ((*n).function)(eval(&n.parameters[0]), eval(&n.parameters[1])),
-                _ => panic!("todo: add more f. pointers (type is {})", arity!(n.e_type))
+            let params = match n.function {
+                // the body of `let` is evaluated once its value is bound, and only one branch of `if`
+                Function::Let | Function::If => &n.parameters[..1],
+                _ => &n.parameters[..],
+            };
+            let mut args = Vec::with_capacity(params.len());
+            for p in params {
+                args.push(eval(p, values, frame, calls)?);
+            }
+            match n.function {
+                Function::Call => call(n, args, calls),
+                Function::Let => let_in_value(n, args, values, frame, calls),
+                Function::If => if_branch(n, &args[0], values, frame, calls),
+                _ => apply(n, args, frame),
             }
         }
-        _ => 0.0
+        _ => Ok(T::default())
+    }
+}
+
+// errors built apart from the recursive functions, whose stack frames stay small in unoptimized builds
+fn too_deep<T>() -> Result<T> {
+    Err(error::TinyExprError::Other(format!("evaluation nested deeper than {}", MAX_EVAL_DEPTH)))
+}
+
+fn too_many_calls<T>() -> Result<T> {
+    Err(error::TinyExprError::Other(format!("calls nested deeper than {}", MAX_CALL_DEPTH)))
+}
+
+fn variable<T: Number>(n: &Expr<T>, values: &[T]) -> Result<T> {
+    match values.get(n.bound) {
+        Some(v) => Ok(v.clone()),
+        None    => Err(error::TinyExprError::Other(format!("no value given for variable {}", n.bound)))
+    }
+}
+
+// the user-defined function of `n` applied to its evaluated arguments
+fn call<T: Number>(n: &Expr<T>, args: Vec<T>, calls: &mut Calls<T>) -> Result<T> {
+    if calls.depth == MAX_CALL_DEPTH {
+        return too_many_calls();
+    }
+    let functions = calls.functions;
+    calls.depth += 1;
+    let ret = eval(&functions[n.bound], &args, &mut Frame::new(), calls);
+    calls.depth -= 1;
+    ret
+}
+
+// the body of `let` with its value bound
+fn let_in_value<T: Number>(n: &Expr<T>, args: Vec<T>, values: &[T], frame: &mut Frame<T>, calls: &mut Calls<T>) -> Result<T> {
+    frame.lets.extend(args);
+    let ret = eval(&n.parameters[1], values, frame, calls);
+    frame.lets.pop();
+    ret
+}
+
+// the `then` branch of `if` unless the condition is zero, the `else` branch otherwise
+fn if_branch<T: Number>(n: &Expr<T>, condition: &T, values: &[T], frame: &mut Frame<T>, calls: &mut Calls<T>) -> Result<T> {
+    let branch = if is_zero(condition)? { 2 } else { 1 };
+    eval(&n.parameters[branch], values, frame, calls)
+}
+
+fn is_zero<T: Number>(x: &T) -> Result<bool> {
+    match x.compare(&T::parse("0")?) {
+        Ok(ordering) => Ok(ordering == Some(Ordering::Equal)),
+        Err(error::TinyExprError::Unsupported(_)) => number::unsupported("if"),
+        Err(err) => Err(err),
+    }
+}
+
+// the function of `n` applied to its evaluated arguments
+fn apply<T: Number>(n: &Expr<T>, mut args: Vec<T>, frame: &mut Frame<T>) -> Result<T> {
+    match n.function {
+        Function::Binding => Ok(frame.lets[n.bound].clone()),
+        // slots are assigned in order, and a local is only read after its assignment
+        Function::Local => Ok(frame.locals[n.bound].clone()),
+        Function::Assign if n.bound == frame.locals.len() => { frame.locals.push(args[0].clone()); Ok(args.remove(0)) },
+        Function::Assign => { frame.locals[n.bound] = args[0].clone(); Ok(args.remove(0)) },
+        _ => n.function.call(args)
     }
 }
@@ -0,0 +1,574 @@
+//! Numeric types expressions can be evaluated in.
+
+use error::{Result, TinyExprError};
+use distributions;
+use number_theory;
+use random;
+use special;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// Numeric type used to evaluate an expression.
+///
+/// Parsing does not depend on the numeric type: literals are kept as written and only converted
+/// with `parse` once the expression is evaluated. Every operator and built-in function has
+/// a matching method, so adding support for a new numeric type comes down to implementing this trait.
+pub trait Number: Clone + Default + fmt::Debug {
+    /// Converts a numeric literal, exactly as it appears in the expression, to a value.
+    fn parse(literal: &str) -> Result<Self>;
+
+    /// Addition (`+`).
+    fn add(self, rhs: Self) -> Result<Self>;
+    /// Subtraction (`-`).
+    fn sub(self, rhs: Self) -> Result<Self>;
+    /// Multiplication (`*`).
+    fn mul(self, rhs: Self) -> Result<Self>;
+    /// Division (`/`).
+    fn div(self, rhs: Self) -> Result<Self>;
+    /// Remainder (`%`).
+    fn fmod(self, rhs: Self) -> Result<Self>;
+    /// Unary negation.
+    fn neg(self) -> Result<Self>;
+    /// Exponentiation (`^` and `pow`).
+    fn pow(self, rhs: Self) -> Result<Self>;
+
+    /// Euler's number (`e`).
+    fn e() -> Result<Self>;
+    /// Archimedes' constant (`pi`).
+    fn pi() -> Result<Self>;
+
+    fn abs(self) -> Result<Self>;
+    fn acos(self) -> Result<Self>;
+    fn asin(self) -> Result<Self>;
+    fn atan(self) -> Result<Self>;
+    fn atan2(self, rhs: Self) -> Result<Self>;
+    fn ceil(self) -> Result<Self>;
+    fn cos(self) -> Result<Self>;
+    fn cosh(self) -> Result<Self>;
+    fn exp(self) -> Result<Self>;
+    fn floor(self) -> Result<Self>;
+    /// Natural logarithm.
+    fn ln(self) -> Result<Self>;
+    /// Common (base 10) logarithm, same as `log10`.
+    fn log(self) -> Result<Self>;
+    fn log10(self) -> Result<Self>;
+    fn sin(self) -> Result<Self>;
+    fn sinh(self) -> Result<Self>;
+    fn sqrt(self) -> Result<Self>;
+    fn tan(self) -> Result<Self>;
+    fn tanh(self) -> Result<Self>;
+
+    /// Imaginary unit (`i`), only available for complex types.
+    fn i() -> Result<Self> { unsupported("i") }
+    /// Real part.
+    fn re(self) -> Result<Self> { unsupported("re") }
+    /// Imaginary part.
+    fn im(self) -> Result<Self> { unsupported("im") }
+    /// Argument (phase angle).
+    fn arg(self) -> Result<Self> { unsupported("arg") }
+    /// Complex conjugate.
+    fn conj(self) -> Result<Self> { unsupported("conj") }
+
+    /// Factorial (`fac`).
+    fn fac(self) -> Result<Self> { unsupported("fac") }
+    /// Number of combinations (`ncr`).
+    fn ncr(self, _r: Self) -> Result<Self> { unsupported("ncr") }
+    /// Number of permutations (`npr`).
+    fn npr(self, _r: Self) -> Result<Self> { unsupported("npr") }
+
+    /// The value as a 64-bit integer, `None` if it is not an integer or does not fit.
+    /// The bitwise operators are available for types implementing it.
+    fn to_i64(&self) -> Result<Option<i64>> { unsupported("bitwise operators") }
+
+    /// Bitwise and (`&`).
+    fn bitand(self, rhs: Self) -> Result<Self> {
+        bitwise("&", &[self, rhs], |x| Ok(x[0] & x[1]))
+    }
+    /// Bitwise or (`|`).
+    fn bitor(self, rhs: Self) -> Result<Self> {
+        bitwise("|", &[self, rhs], |x| Ok(x[0] | x[1]))
+    }
+    /// Bitwise exclusive or (`xor`).
+    fn bitxor(self, rhs: Self) -> Result<Self> {
+        bitwise("xor", &[self, rhs], |x| Ok(x[0] ^ x[1]))
+    }
+    /// Bitwise complement (`~`), `-self - 1` in two's complement.
+    fn bitnot(self) -> Result<Self> {
+        bitwise("~", &[self], |x| Ok(!x[0]))
+    }
+    /// Left shift (`<<`). Bits shifted beyond the sign bit are an error.
+    fn shl(self, rhs: Self) -> Result<Self> {
+        bitwise("<<", &[self, rhs], |x| {
+            let ret = x[0] << shift_amount("<<", x[1])?;
+            if ret >> x[1] == x[0] { Ok(ret) } else { Err(TinyExprError::Other(String::from("result of `<<` does not fit in 64 bits"))) }
+        })
+    }
+    /// Arithmetic right shift (`>>`), rounding towards negative infinity.
+    fn shr(self, rhs: Self) -> Result<Self> {
+        bitwise(">>", &[self, rhs], |x| Ok(x[0] >> shift_amount(">>", x[1])?))
+    }
+
+    /// Ordering of two values, `None` if they are unordered like NaN. Used by `min`, `max` and `median`.
+    fn compare(&self, _rhs: &Self) -> Result<Option<Ordering>> { unsupported("comparison") }
+
+    /// Cube root (`cbrt`), the principal root for complex types.
+    fn cbrt(self) -> Result<Self> {
+        self.pow(Self::parse("1")?.div(Self::parse("3")?)?)
+    }
+    /// Length of the hypotenuse, `sqrt(self^2 + y^2)` (`hypot`).
+    fn hypot(self, y: Self) -> Result<Self> {
+        self.clone().mul(self)?.add(y.clone().mul(y)?)?.sqrt()
+    }
+    /// Base 2 logarithm (`log2`).
+    fn log2(self) -> Result<Self> {
+        self.ln()?.div(Self::parse("2")?.ln()?)
+    }
+    /// `ln(1 + self)`, accurate for small values where the type allows it (`log1p`).
+    fn log1p(self) -> Result<Self> {
+        Self::parse("1")?.add(self)?.ln()
+    }
+    /// `exp(self) - 1`, accurate for small values where the type allows it (`expm1`).
+    fn expm1(self) -> Result<Self> {
+        self.exp()?.sub(Self::parse("1")?)
+    }
+    /// Inverse hyperbolic sine (`asinh`).
+    fn asinh(self) -> Result<Self> {
+        let root = self.clone().mul(self.clone())?.add(Self::parse("1")?)?.sqrt()?;
+        self.add(root)?.ln()
+    }
+    /// Inverse hyperbolic cosine (`acosh`).
+    fn acosh(self) -> Result<Self> {
+        let one = Self::parse("1")?;
+        let root = self.clone().add(one.clone())?.sqrt()?.mul(self.clone().sub(one)?.sqrt()?)?;
+        self.add(root)?.ln()
+    }
+    /// Inverse hyperbolic tangent (`atanh`).
+    fn atanh(self) -> Result<Self> {
+        let one = Self::parse("1")?;
+        let ln_ratio = one.clone().add(self.clone())?.ln()?.sub(one.sub(self)?.ln()?)?;
+        ln_ratio.div(Self::parse("2")?)
+    }
+    /// Secant, `1 / cos` (`sec`).
+    fn sec(self) -> Result<Self> {
+        Self::parse("1")?.div(self.cos()?)
+    }
+    /// Cosecant, `1 / sin` (`csc`).
+    fn csc(self) -> Result<Self> {
+        Self::parse("1")?.div(self.sin()?)
+    }
+    /// Cotangent, `cos / sin` (`cot`).
+    fn cot(self) -> Result<Self> {
+        self.clone().cos()?.div(self.sin()?)
+    }
+
+    /// Converts radians to degrees (`deg`).
+    fn deg(self) -> Result<Self> {
+        self.mul(Self::parse("180")?)?.div(Self::pi()?)
+    }
+    /// Converts degrees to radians (`rad`).
+    fn rad(self) -> Result<Self> {
+        self.mul(Self::pi()?)?.div(Self::parse("180")?)
+    }
+
+    /// Rounds to the nearest integer, halfway cases away from zero (`round`).
+    fn round(self) -> Result<Self> {
+        // compares twice the fractional part with one, which is exact for every type
+        let one = Self::parse("1")?;
+        let f = self.clone().floor()?;
+        let twice = self.clone().sub(f.clone())?.mul(Self::parse("2")?)?;
+        match twice.compare(&one)? {
+            Some(Ordering::Greater) => f.add(one),
+            Some(Ordering::Equal) if self.compare(&Self::default())? != Some(Ordering::Less) => f.add(one),
+            _ => Ok(f),
+        }
+    }
+    /// Rounds to the given number of decimal digits, which may be negative (`round` with two arguments).
+    fn round_digits(self, digits: Self) -> Result<Self> {
+        let ten = Self::parse("10")?;
+        if digits.compare(&Self::default())? == Some(Ordering::Less) {
+            let step = ten.pow(digits.neg()?)?;
+            self.div(step.clone())?.round()?.mul(step)
+        } else {
+            let scale = ten.pow(digits)?;
+            self.mul(scale.clone())?.round()?.div(scale)
+        }
+    }
+    /// Rounds to the nearest multiple of `step` (`roundto`).
+    fn roundto(self, step: Self) -> Result<Self> {
+        self.div(step.clone())?.round()?.mul(step)
+    }
+    /// Rounds towards zero (`trunc`).
+    fn trunc(self) -> Result<Self> {
+        if self.compare(&Self::default())? == Some(Ordering::Less) { self.ceil() } else { self.floor() }
+    }
+    /// Fractional part, with the sign of `self` (`fract`).
+    fn fract(self) -> Result<Self> {
+        self.clone().sub(self.trunc()?)
+    }
+    /// -1, 0 or 1 depending on the sign (`sign`). Zero and NaN are returned as they are.
+    fn sign(self) -> Result<Self> {
+        match self.compare(&Self::default())? {
+            Some(Ordering::Less) => Self::parse("1")?.neg(),
+            Some(Ordering::Greater) => Self::parse("1"),
+            _ => Ok(self),
+        }
+    }
+    /// Limits the value to the range `[lo, hi]` (`clamp`). NaN is returned as it is.
+    fn clamp(self, lo: Self, hi: Self) -> Result<Self> {
+        if lo.compare(&hi)? == Some(Ordering::Greater) {
+            return Err(TinyExprError::Other(String::from("lower bound of `clamp` is greater than its upper bound")));
+        }
+        if self.compare(&lo)? == Some(Ordering::Less) {
+            Ok(lo)
+        } else if self.compare(&hi)? == Some(Ordering::Greater) {
+            Ok(hi)
+        } else {
+            Ok(self)
+        }
+    }
+    /// Euclidean remainder, never negative for a non-zero `rhs` (`mod`).
+    fn rem_euclid(self, rhs: Self) -> Result<Self> {
+        let r = self.fmod(rhs.clone())?;
+        if r.compare(&Self::default())? == Some(Ordering::Less) { r.add(rhs.abs()?) } else { Ok(r) }
+    }
+
+    /// Greatest common divisor, never negative (`gcd`). Requires integers.
+    fn gcd(self, rhs: Self) -> Result<Self> {
+        let (mut a, mut b) = (integral(self, "gcd")?.abs()?, integral(rhs, "gcd")?.abs()?);
+        while b.compare(&Self::default())? == Some(Ordering::Greater) {
+            let r = a.fmod(b.clone())?;
+            a = b;
+            b = r;
+        }
+        Ok(a)
+    }
+    /// Least common multiple, never negative (`lcm`). Requires integers.
+    fn lcm(self, rhs: Self) -> Result<Self> {
+        let g = self.clone().gcd(rhs.clone())?;
+        if g.compare(&Self::default())? == Some(Ordering::Equal) {
+            return Ok(g);
+        }
+        self.div(g)?.mul(rhs)?.abs()
+    }
+    /// Integer quotient matching `mod`, so that `a = b*idiv(a, b) + mod(a, b)` (`idiv`). Requires integers.
+    fn idiv(self, rhs: Self) -> Result<Self> {
+        let (a, b) = (integral(self, "idiv")?, integral(rhs, "idiv")?);
+        let r = a.clone().rem_euclid(b.clone())?;
+        a.sub(r)?.div(b)
+    }
+    /// `self^exp mod m`, in `[0, |m|)`, without computing the full power (`powmod`).
+    /// Requires integers and a non-negative exponent.
+    fn powmod(self, exp: Self, m: Self) -> Result<Self> {
+        let (base, mut exp, m) = (integral(self, "powmod")?, non_negative_exponent(integral(exp, "powmod")?)?, integral(m, "powmod")?);
+        let (one, two) = (Self::parse("1")?, Self::parse("2")?);
+        let mut base = base.rem_euclid(m.clone())?;
+        let mut ret = one.clone().rem_euclid(m.clone())?;
+        while exp.compare(&Self::default())? == Some(Ordering::Greater) {
+            if exp.clone().fmod(two.clone())?.compare(&one)? == Some(Ordering::Equal) {
+                ret = ret.mul(base.clone())?.rem_euclid(m.clone())?;
+            }
+            base = base.clone().mul(base)?.rem_euclid(m.clone())?;
+            exp = exp.div(two.clone())?.floor()?;
+        }
+        Ok(ret)
+    }
+    /// 1 if the value is a prime number, otherwise 0 (`isprime`). Requires an integer.
+    ///
+    /// The default implementation uses trial division.
+    fn isprime(self) -> Result<Self> {
+        let n = integral(self, "isprime")?;
+        let (zero, one) = (Self::default(), Self::parse("1")?);
+        let mut d = Self::parse("2")?;
+        if n.compare(&d)? == Some(Ordering::Less) {
+            return Ok(zero);
+        }
+        while d.clone().mul(d.clone())?.compare(&n)? != Some(Ordering::Greater) {
+            if n.clone().fmod(d.clone())?.compare(&zero)? == Some(Ordering::Equal) {
+                return Ok(zero);
+            }
+            d = d.add(one.clone())?;
+        }
+        Ok(one)
+    }
+
+    /// Uniformly distributed random number in `[0, 1)` (`rand`), rounded to the precision of the type.
+    fn rand() -> Result<Self> {
+        Self::parse(&random::uniform(53).to_string())
+    }
+    /// Uniformly distributed random integer in `[self, hi]` (`randint`). Requires integers.
+    fn randint(self, hi: Self) -> Result<Self> {
+        let (lo, hi) = (integral(self, "randint")?, integral(hi, "randint")?);
+        if lo.compare(&hi)? == Some(Ordering::Greater) {
+            return Err(TinyExprError::Other(String::from("lower bound of `randint` is greater than its upper bound")));
+        }
+        let count = hi.clone().sub(lo.clone())?.add(Self::parse("1")?)?;
+        let k = Self::rand()?.mul(count.clone())?.floor()?;
+        // `rand` may have been rounded up to 1
+        if k.compare(&count)? == Some(Ordering::Less) { lo.add(k) } else { Ok(hi) }
+    }
+    /// Normally distributed random number with mean `self` and standard deviation `sigma` (`randn`).
+    fn randn(self, sigma: Self) -> Result<Self> {
+        // literals have no sign in every number type
+        let z = distributions::norm_inv(random::open_uniform(), 0.0, 1.0);
+        let magnitude = Self::parse(&z.abs().to_string())?;
+        let z = if z < 0.0 { magnitude.neg()? } else { magnitude };
+        self.add(sigma.mul(z)?)
+    }
+
+    /// Density of the normal distribution with mean `mu` and standard deviation `sigma` (`normpdf`).
+    fn normpdf(self, _mu: Self, _sigma: Self) -> Result<Self> { unsupported("normpdf") }
+    /// Cumulative distribution function of the normal distribution (`normcdf`).
+    fn normcdf(self, _mu: Self, _sigma: Self) -> Result<Self> { unsupported("normcdf") }
+    /// Quantile function of the normal distribution (`norminv`).
+    fn norminv(self, _mu: Self, _sigma: Self) -> Result<Self> { unsupported("norminv") }
+    /// Density of the lognormal distribution (`lognpdf`).
+    fn lognpdf(self, _mu: Self, _sigma: Self) -> Result<Self> { unsupported("lognpdf") }
+    /// Cumulative distribution function of the lognormal distribution (`logncdf`).
+    fn logncdf(self, _mu: Self, _sigma: Self) -> Result<Self> { unsupported("logncdf") }
+    /// Quantile function of the lognormal distribution (`logninv`).
+    fn logninv(self, _mu: Self, _sigma: Self) -> Result<Self> { unsupported("logninv") }
+    /// Probability of `k` successes in `n` trials with success probability `p` (`binompdf`).
+    fn binompdf(self, _n: Self, _p: Self) -> Result<Self> { unsupported("binompdf") }
+    /// Probability of at most `k` successes in `n` trials (`binomcdf`).
+    fn binomcdf(self, _n: Self, _p: Self) -> Result<Self> { unsupported("binomcdf") }
+    /// Quantile function of the binomial distribution (`binominv`).
+    fn binominv(self, _n: Self, _p: Self) -> Result<Self> { unsupported("binominv") }
+    /// Probability of `k` events of a Poisson distribution with mean `lambda` (`poisspdf`).
+    fn poisspdf(self, _lambda: Self) -> Result<Self> { unsupported("poisspdf") }
+    /// Probability of at most `k` events of a Poisson distribution (`poisscdf`).
+    fn poisscdf(self, _lambda: Self) -> Result<Self> { unsupported("poisscdf") }
+    /// Quantile function of the Poisson distribution (`poissinv`).
+    fn poissinv(self, _lambda: Self) -> Result<Self> { unsupported("poissinv") }
+    /// Density of the exponential distribution with rate `lambda` (`exppdf`).
+    fn exppdf(self, _lambda: Self) -> Result<Self> { unsupported("exppdf") }
+    /// Cumulative distribution function of the exponential distribution (`expcdf`).
+    fn expcdf(self, _lambda: Self) -> Result<Self> { unsupported("expcdf") }
+    /// Quantile function of the exponential distribution (`expinv`).
+    fn expinv(self, _lambda: Self) -> Result<Self> { unsupported("expinv") }
+    /// Density of Student's t distribution with `nu` degrees of freedom (`tpdf`).
+    fn tpdf(self, _nu: Self) -> Result<Self> { unsupported("tpdf") }
+    /// Cumulative distribution function of Student's t distribution (`tcdf`).
+    fn tcdf(self, _nu: Self) -> Result<Self> { unsupported("tcdf") }
+    /// Quantile function of Student's t distribution (`tinv`).
+    fn tinv(self, _nu: Self) -> Result<Self> { unsupported("tinv") }
+
+    /// Gamma function.
+    fn gamma(self) -> Result<Self> { unsupported("gamma") }
+    /// Natural logarithm of the absolute value of the gamma function (`lgamma`).
+    fn lgamma(self) -> Result<Self> { unsupported("lgamma") }
+    /// Error function.
+    fn erf(self) -> Result<Self> { unsupported("erf") }
+    /// Complementary error function.
+    fn erfc(self) -> Result<Self> { unsupported("erfc") }
+    /// Beta function.
+    fn beta(self, _b: Self) -> Result<Self> { unsupported("beta") }
+    /// Regularized lower incomplete gamma function P(self, x) (`gammainc`).
+    fn gammainc(self, _x: Self) -> Result<Self> { unsupported("gammainc") }
+    /// Regularized upper incomplete gamma function Q(self, x) (`gammaincc`).
+    fn gammaincc(self, _x: Self) -> Result<Self> { unsupported("gammaincc") }
+}
+
+/// Error returned by operations a numeric type does not implement.
+pub fn unsupported<T>(op: &str) -> Result<T> {
+    Err(TinyExprError::Unsupported(String::from(op)))
+}
+
+// applies `op` to the operands converted to 64-bit integers
+fn bitwise<T: Number, F: Fn(&[i64]) -> Result<i64>>(op: &str, operands: &[T], f: F) -> Result<T> {
+    let mut ints = Vec::with_capacity(operands.len());
+    for x in operands {
+        match x.to_i64()? {
+            Some(n) => ints.push(n),
+            None => return Err(TinyExprError::Other(format!("operands of `{}` must be integers within 64 bits", op))),
+        }
+    }
+    let n = f(&ints)?;
+    let abs = T::parse(&n.unsigned_abs().to_string())?;
+    if n < 0 { abs.neg() } else { Ok(abs) }
+}
+
+fn shift_amount(op: &str, n: i64) -> Result<u32> {
+    if (0..64).contains(&n) {
+        Ok(n as u32)
+    } else {
+        Err(TinyExprError::Other(format!("shift amount of `{}` must be between 0 and 63", op)))
+    }
+}
+
+// `x` itself if it is an integer, otherwise an error naming the builtin which needs one
+pub(crate) fn integral<T: Number>(x: T, op: &str) -> Result<T> {
+    if x.clone().fract()?.compare(&T::default())? == Some(Ordering::Equal) {
+        Ok(x)
+    } else {
+        Err(TinyExprError::Other(format!("`{}` expects integer arguments", op)))
+    }
+}
+
+fn non_negative_exponent<T: Number>(exp: T) -> Result<T> {
+    if exp.compare(&T::default())? == Some(Ordering::Less) {
+        Err(TinyExprError::Other(String::from("exponent of `powmod` is negative")))
+    } else {
+        Ok(exp)
+    }
+}
+
+// integer checks are done on the f64 value, which is exact for f32 too
+fn powmod_float(base: f64, exp: f64, m: f64) -> Result<f64> {
+    let (base, exp, m) = (integral(base, "powmod")?, non_negative_exponent(integral(exp, "powmod")?)?, integral(m, "powmod")?);
+    if m == 0.0 {
+        // same as `mod` by zero
+        Ok(f64::NAN)
+    } else if m.abs() >= 18446744073709551616.0 {
+        Err(TinyExprError::Other(String::from("modulus of `powmod` is too large")))
+    } else {
+        Ok(number_theory::pow_mod(base, exp, m))
+    }
+}
+
+fn randint_float(lo: f64, hi: f64) -> Result<f64> {
+    let (lo, hi) = (integral(lo, "randint")?, integral(hi, "randint")?);
+    if lo > hi {
+        Err(TinyExprError::Other(String::from("lower bound of `randint` is greater than its upper bound")))
+    } else if hi - lo >= 18446744073709551616.0 {
+        Err(TinyExprError::Other(String::from("range of `randint` is too large")))
+    } else {
+        Ok(lo + random::at_most((hi - lo) as u64) as f64)
+    }
+}
+
+fn float_to_i64(x: f64) -> Option<i64> {
+    // the range check is exact as both bounds are powers of two
+    if x.fract() == 0.0 && (-9223372036854775808.0..9223372036854775808.0).contains(&x) { Some(x as i64) } else { None }
+}
+
+fn isprime_float(n: f64) -> Result<f64> {
+    // doubles from 2^53 on are all even
+    let n = integral(n, "isprime")?;
+    Ok(if n < 18446744073709551616.0 && number_theory::is_prime(n as u64) { 1.0 } else { 0.0 })
+}
+
+// rounds x to `digits` decimals through the nearest double of x * 10^digits
+fn round_digits(x: f64, digits: f64) -> f64 {
+    if digits.fract() != 0.0 {
+        return f64::NAN;
+    }
+    let p = 10f64.powf(digits.abs());
+    if p.is_infinite() {
+        return if digits < 0.0 && x.is_finite() { 0.0 * x.signum() } else { x };
+    }
+    let scaled = if digits < 0.0 { x / p } else { x * p };
+    if scaled.is_nan() || scaled.abs() >= 4503599627370496.0 {
+        // no digits left to round at this position, or NaN or infinite
+        x
+    } else if digits < 0.0 {
+        scaled.round() * p
+    } else {
+        scaled.round() / p
+    }
+}
+
+macro_rules! impl_float {
+    ($t:ident) => {
+        impl Number for $t {
+            fn parse(literal: &str) -> Result<$t> { Ok($t::from_str(literal)?) }
+
+            fn   add(self, rhs: $t) -> Result<$t> { Ok(self + rhs) }
+            fn   sub(self, rhs: $t) -> Result<$t> { Ok(self - rhs) }
+            fn   mul(self, rhs: $t) -> Result<$t> { Ok(self * rhs) }
+            fn   div(self, rhs: $t) -> Result<$t> { Ok(self / rhs) }
+            fn  fmod(self, rhs: $t) -> Result<$t> { Ok(self % rhs) }
+            fn   neg(self)          -> Result<$t> { Ok(-self) }
+            fn   pow(self, rhs: $t) -> Result<$t> { Ok(self.powf(rhs)) }
+
+            fn e()  -> Result<$t> { Ok(::std::$t::consts::E)  }
+            fn pi() -> Result<$t> { Ok(::std::$t::consts::PI) }
+
+            fn   abs(self)          -> Result<$t> { Ok($t::abs(self))        }
+            fn  acos(self)          -> Result<$t> { Ok($t::acos(self))       }
+            fn  asin(self)          -> Result<$t> { Ok($t::asin(self))       }
+            fn  atan(self)          -> Result<$t> { Ok($t::atan(self))       }
+            fn atan2(self, rhs: $t) -> Result<$t> { Ok($t::atan2(self, rhs)) }
+            fn  ceil(self)          -> Result<$t> { Ok($t::ceil(self))       }
+            fn   cos(self)          -> Result<$t> { Ok($t::cos(self))        }
+            fn  cosh(self)          -> Result<$t> { Ok($t::cosh(self))       }
+            fn   exp(self)          -> Result<$t> { Ok($t::exp(self))        }
+            fn floor(self)          -> Result<$t> { Ok($t::floor(self))      }
+            fn    ln(self)          -> Result<$t> { Ok($t::ln(self))         }
+            fn   log(self)          -> Result<$t> { Ok($t::log10(self))      }
+            fn log10(self)          -> Result<$t> { Ok($t::log10(self))      }
+            fn   sin(self)          -> Result<$t> { Ok($t::sin(self))        }
+            fn  sinh(self)          -> Result<$t> { Ok($t::sinh(self))       }
+            fn  sqrt(self)          -> Result<$t> { Ok($t::sqrt(self))       }
+            fn   tan(self)          -> Result<$t> { Ok($t::tan(self))        }
+            fn  tanh(self)          -> Result<$t> { Ok($t::tanh(self))       }
+
+            fn    re(self)          -> Result<$t> { Ok(self)                 }
+            fn    im(self)          -> Result<$t> { Ok(0.0)                  }
+            fn   arg(self)          -> Result<$t> { Ok($t::atan2(0.0, self)) }
+            fn  conj(self)          -> Result<$t> { Ok(self)                 }
+
+            fn compare(&self, rhs: &$t) -> Result<Option<Ordering>> { Ok(self.partial_cmp(rhs)) }
+            fn  to_i64(&self)           -> Result<Option<i64>>      { Ok(float_to_i64((*self).into())) }
+
+            fn  cbrt(self)          -> Result<$t> { Ok($t::cbrt(self))       }
+            fn hypot(self, y: $t)   -> Result<$t> { Ok($t::hypot(self, y))   }
+            fn  log2(self)          -> Result<$t> { Ok($t::log2(self))       }
+            fn log1p(self)          -> Result<$t> { Ok($t::ln_1p(self))      }
+            fn expm1(self)          -> Result<$t> { Ok($t::exp_m1(self))     }
+            fn asinh(self)          -> Result<$t> { Ok($t::asinh(self))      }
+            fn acosh(self)          -> Result<$t> { Ok($t::acosh(self))      }
+            fn atanh(self)          -> Result<$t> { Ok($t::atanh(self))      }
+            fn   deg(self)          -> Result<$t> { Ok($t::to_degrees(self)) }
+            fn   rad(self)          -> Result<$t> { Ok($t::to_radians(self)) }
+
+            fn        round(self)               -> Result<$t> { Ok($t::round(self))                           }
+            fn round_digits(self, digits: $t)   -> Result<$t> { Ok(round_digits(self.into(), digits.into()) as $t) }
+            fn        trunc(self)               -> Result<$t> { Ok($t::trunc(self))                           }
+            fn        fract(self)               -> Result<$t> { Ok($t::fract(self))                           }
+            fn   rem_euclid(self, rhs: $t)      -> Result<$t> { Ok($t::rem_euclid(self, rhs))                 }
+
+            fn  powmod(self, exp: $t, m: $t) -> Result<$t> { Ok(powmod_float(self.into(), exp.into(), m.into())? as $t) }
+            fn isprime(self)                 -> Result<$t> { Ok(isprime_float(self.into())? as $t)                       }
+
+            fn    rand()                     -> Result<$t> { Ok(random::uniform($t::MANTISSA_DIGITS) as $t)                  }
+            fn randint(self, hi: $t)         -> Result<$t> { Ok(randint_float(self.into(), hi.into())? as $t)                }
+            fn   randn(self, sigma: $t)      -> Result<$t> { Ok(distributions::norm_inv(random::open_uniform(), self.into(), sigma.into()) as $t) }
+
+            // computed in f64 and rounded once to the target type
+            fn   fac(self)          -> Result<$t> { Ok(special::factorial(self.into()) as $t) }
+            fn   ncr(self, r: $t)   -> Result<$t> { Ok(special::binomial(self.into(), r.into()) as $t) }
+            fn   npr(self, r: $t)   -> Result<$t> { Ok(special::permutations(self.into(), r.into()) as $t) }
+
+            fn  normpdf(self, mu: $t, sigma: $t) -> Result<$t> { Ok(distributions::norm_pdf(self.into(), mu.into(), sigma.into()) as $t) }
+            fn  normcdf(self, mu: $t, sigma: $t) -> Result<$t> { Ok(distributions::norm_cdf(self.into(), mu.into(), sigma.into()) as $t) }
+            fn  norminv(self, mu: $t, sigma: $t) -> Result<$t> { Ok(distributions::norm_inv(self.into(), mu.into(), sigma.into()) as $t) }
+            fn  lognpdf(self, mu: $t, sigma: $t) -> Result<$t> { Ok(distributions::logn_pdf(self.into(), mu.into(), sigma.into()) as $t) }
+            fn  logncdf(self, mu: $t, sigma: $t) -> Result<$t> { Ok(distributions::logn_cdf(self.into(), mu.into(), sigma.into()) as $t) }
+            fn  logninv(self, mu: $t, sigma: $t) -> Result<$t> { Ok(distributions::logn_inv(self.into(), mu.into(), sigma.into()) as $t) }
+            fn binompdf(self, n: $t, p: $t) -> Result<$t> { Ok(distributions::binom_pdf(self.into(), n.into(), p.into()) as $t) }
+            fn binomcdf(self, n: $t, p: $t) -> Result<$t> { Ok(distributions::binom_cdf(self.into(), n.into(), p.into()) as $t) }
+            fn binominv(self, n: $t, p: $t) -> Result<$t> { Ok(distributions::binom_inv(self.into(), n.into(), p.into()) as $t) }
+            fn poisspdf(self, lambda: $t) -> Result<$t> { Ok(distributions::poiss_pdf(self.into(), lambda.into()) as $t) }
+            fn poisscdf(self, lambda: $t) -> Result<$t> { Ok(distributions::poiss_cdf(self.into(), lambda.into()) as $t) }
+            fn poissinv(self, lambda: $t) -> Result<$t> { Ok(distributions::poiss_inv(self.into(), lambda.into()) as $t) }
+            fn   exppdf(self, lambda: $t) -> Result<$t> { Ok(distributions::exp_pdf(self.into(), lambda.into()) as $t) }
+            fn   expcdf(self, lambda: $t) -> Result<$t> { Ok(distributions::exp_cdf(self.into(), lambda.into()) as $t) }
+            fn   expinv(self, lambda: $t) -> Result<$t> { Ok(distributions::exp_inv(self.into(), lambda.into()) as $t) }
+            fn     tpdf(self, nu: $t) -> Result<$t> { Ok(distributions::t_pdf(self.into(), nu.into()) as $t) }
+            fn     tcdf(self, nu: $t) -> Result<$t> { Ok(distributions::t_cdf(self.into(), nu.into()) as $t) }
+            fn     tinv(self, nu: $t) -> Result<$t> { Ok(distributions::t_inv(self.into(), nu.into()) as $t) }
+
+            fn     gamma(self)          -> Result<$t> { Ok(special::gamma(self.into()) as $t)                 }
+            fn    lgamma(self)          -> Result<$t> { Ok(special::ln_gamma(self.into()) as $t)              }
+            fn       erf(self)          -> Result<$t> { Ok(special::erf(self.into()) as $t)                   }
+            fn      erfc(self)          -> Result<$t> { Ok(special::erfc(self.into()) as $t)                  }
+            fn      beta(self, b: $t)   -> Result<$t> { Ok(special::beta(self.into(), b.into()) as $t)        }
+            fn  gammainc(self, x: $t)   -> Result<$t> { Ok(special::gamma_p(self.into(), x.into()) as $t)     }
+            fn gammaincc(self, x: $t)   -> Result<$t> { Ok(special::gamma_q(self.into(), x.into()) as $t)     }
+        }
+    }
+}
+
+impl_float!(f32);
+impl_float!(f64);
@@ -0,0 +1,70 @@
+//! Integer algorithms behind `isprime` and `powmod` for the floating point number types.
+//!
+//! Doubles represent integers exactly up to 2^53, so these work on `u64` with `u128` products
+//! instead of going through the generic `Number` implementations, whose intermediate products
+//! would be rounded.
+
+// (a * b) mod m without overflow
+fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+fn pow_mod_u64(mut base: u64, mut exp: u64, m: u64) -> u64 {
+    let mut ret = 1 % m;
+    base %= m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            ret = mul_mod(ret, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    ret
+}
+
+/// `base^exp mod modulus`, in `[0, |modulus|)`. All arguments must be integers, `exp` must not be
+/// negative and `|modulus|` must be non-zero and below 2^64.
+pub fn pow_mod(base: f64, mut exp: f64, modulus: f64) -> f64 {
+    let m = modulus.abs() as u64;
+    let mut base = base.rem_euclid(modulus.abs()) as u64;
+    let mut ret = 1 % m;
+    // the exponent may exceed u64, halving a double is exact
+    while exp > 0.0 {
+        if exp % 2.0 == 1.0 {
+            ret = mul_mod(ret, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp = (exp / 2.0).floor();
+    }
+    ret as f64
+}
+
+/// Deterministic Miller-Rabin test, exact for every `u64`.
+pub fn is_prime(n: u64) -> bool {
+    // the first twelve primes are enough witnesses for all n below 3.3 * 10^24
+    const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    for &p in &WITNESSES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    'witness: for &a in &WITNESSES {
+        let mut x = pow_mod_u64(a, d, n);
+        if x == 1 || x == n - 1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
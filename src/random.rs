@@ -0,0 +1,79 @@
+//! Pseudo-random numbers behind `rand`, `randint` and `randn`.
+//!
+//! Every thread has its own xoshiro256** generator. It starts from a seed taken from the
+//! standard library's random hash keys, so results differ between runs unless `seed` is called.
+
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+thread_local! {
+    static STATE: Cell<[u64; 4]> = Cell::new(from_seed(random_seed()));
+}
+
+fn random_seed() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(0);
+    hasher.finish()
+}
+
+// expands a single seed into a full state with splitmix64, which never yields the all-zero state
+fn from_seed(mut seed: u64) -> [u64; 4] {
+    let mut state = [0; 4];
+    for s in &mut state {
+        seed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = seed;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        *s = z ^ (z >> 31);
+    }
+    state
+}
+
+/// Restarts the generator of the current thread from `seed`.
+pub fn seed(seed: u64) {
+    STATE.with(|s| s.set(from_seed(seed)));
+}
+
+fn next_u64() -> u64 {
+    STATE.with(|cell| {
+        let mut s = cell.get();
+        let ret = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        cell.set(s);
+        ret
+    })
+}
+
+/// Uniformly distributed multiple of 2^-bits in `[0, 1)`, exact in any float type with
+/// at least `bits` mantissa digits.
+pub fn uniform(bits: u32) -> f64 {
+    (next_u64() >> (64 - bits)) as f64 / 2f64.powi(bits as i32)
+}
+
+/// Uniformly distributed integer in `[0, n]`.
+pub fn at_most(n: u64) -> u64 {
+    if n == u64::MAX {
+        return next_u64();
+    }
+    // rejects the top partial copy of [0, n] to avoid any bias
+    let range = n + 1;
+    let limit = u64::MAX - u64::MAX % range;
+    loop {
+        let x = next_u64();
+        if x < limit {
+            return x % range;
+        }
+    }
+}
+
+/// Uniformly distributed in `(0, 1)`, the midpoints of the steps of `uniform(52)`.
+pub fn open_uniform() -> f64 {
+    uniform(52) + 2f64.powi(-53)
+}
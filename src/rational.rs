@@ -0,0 +1,193 @@
+//! Exact rational numbers for evaluation without rounding errors.
+
+use bigint::BigInt;
+use error::{Result, TinyExprError};
+use number::{integral, unsupported, Number};
+use number_theory;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Exact fraction of two arbitrary precision integers, always kept in lowest terms
+/// with a positive denominator.
+///
+/// Arithmetic operators, `abs`, `ceil`, `floor` and powers with integer exponents are exact,
+/// so `1/3*3` evaluates to exactly `1`. Functions with irrational results such as `sin` or `sqrt`
+/// are reported as `TinyExprError::Unsupported`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rational {
+    numer: BigInt,
+    denom: BigInt,
+}
+
+impl Rational {
+    /// Creates the fraction `numer / denom`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `denom` is zero.
+    pub fn new(numer: i64, denom: i64) -> Rational {
+        Rational::from_parts(BigInt::from_i64(numer), BigInt::from_i64(denom)).expect("zero denominator")
+    }
+
+    fn from_integer(v: BigInt) -> Rational {
+        Rational { numer: v, denom: BigInt::one() }
+    }
+
+    fn from_parts(numer: BigInt, denom: BigInt) -> Result<Rational> {
+        if denom.is_zero() {
+            return Err(TinyExprError::DivisionByZero);
+        }
+        let g = numer.gcd(&denom);
+        let (mut numer, mut denom) = (numer.divrem(&g).unwrap().0, denom.divrem(&g).unwrap().0);
+        if denom.is_negative() {
+            numer = numer.neg();
+            denom = denom.neg();
+        }
+        Ok(Rational { numer, denom })
+    }
+
+    /// Returns `true` if the denominator is one.
+    pub fn is_integer(&self) -> bool {
+        self.denom == BigInt::one()
+    }
+
+    /// Nearest `f64` to this fraction.
+    pub fn to_f64(&self) -> f64 {
+        self.numer.ratio_to_f64(&self.denom)
+    }
+
+    // rounds towards zero
+    fn trunc_integer(&self) -> BigInt {
+        self.numer.divrem(&self.denom).unwrap().0
+    }
+
+    fn floor(&self) -> BigInt {
+        let t = self.trunc_integer();
+        if self.numer.is_negative() && !self.is_integer() { t.sub(&BigInt::one()) } else { t }
+    }
+}
+
+impl Default for Rational {
+    fn default() -> Rational {
+        Rational::from_integer(BigInt::zero())
+    }
+}
+
+/// Formats as `numer/denom`, or just `numer` for integers.
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_integer() {
+            f.pad(&self.numer.to_string())
+        } else {
+            f.pad(&format!("{}/{}", self.numer, self.denom))
+        }
+    }
+}
+
+impl Number for Rational {
+    fn parse(literal: &str) -> Result<Rational> {
+        // "12.375" is 12375/1000
+        match BigInt::parse_decimal(literal) {
+            Some((digits, scale)) => Rational::from_parts(digits, BigInt::from_u64(10).pow(scale as u64)),
+            None => Err(TinyExprError::Other(format!("invalid number literal `{}`", literal))),
+        }
+    }
+
+    fn add(self, rhs: Rational) -> Result<Rational> {
+        Rational::from_parts(self.numer.mul(&rhs.denom).add(&rhs.numer.mul(&self.denom)), self.denom.mul(&rhs.denom))
+    }
+
+    fn sub(self, rhs: Rational) -> Result<Rational> {
+        Rational::from_parts(self.numer.mul(&rhs.denom).sub(&rhs.numer.mul(&self.denom)), self.denom.mul(&rhs.denom))
+    }
+
+    fn mul(self, rhs: Rational) -> Result<Rational> {
+        Rational::from_parts(self.numer.mul(&rhs.numer), self.denom.mul(&rhs.denom))
+    }
+
+    fn div(self, rhs: Rational) -> Result<Rational> {
+        Rational::from_parts(self.numer.mul(&rhs.denom), self.denom.mul(&rhs.numer))
+    }
+
+    fn fmod(self, rhs: Rational) -> Result<Rational> {
+        // a - b*trunc(a/b), same sign convention as fmod on floats
+        let q = Number::div(self.clone(), rhs.clone())?.trunc_integer();
+        Number::sub(self, Number::mul(rhs, Rational::from_integer(q))?)
+    }
+
+    fn neg(self) -> Result<Rational> {
+        Ok(Rational { numer: self.numer.neg(), denom: self.denom })
+    }
+
+    fn pow(self, rhs: Rational) -> Result<Rational> {
+        if !rhs.is_integer() {
+            return unsupported("pow with a non-integer exponent");
+        }
+        let exp = match rhs.numer.abs().to_i64() {
+            Some(exp) => exp as u64,
+            None => return Err(TinyExprError::Other(String::from("exponent is too large"))),
+        };
+        if self.numer.pow_too_large(exp) || self.denom.pow_too_large(exp) {
+            return Err(TinyExprError::Other(String::from("result of pow is too large")));
+        }
+        let ret = Rational { numer: self.numer.pow(exp), denom: self.denom.pow(exp) };
+        if rhs.numer.is_negative() {
+            Rational::from_parts(ret.denom, ret.numer)
+        } else {
+            Ok(ret)
+        }
+    }
+
+    fn  e() -> Result<Rational> { unsupported("e")  }
+    fn pi() -> Result<Rational> { unsupported("pi") }
+
+    fn abs(self) -> Result<Rational> {
+        Ok(Rational { numer: self.numer.abs(), denom: self.denom })
+    }
+
+    fn ceil(self) -> Result<Rational> {
+        Ok(Rational::from_integer(Rational::floor(&self.neg()?).neg()))
+    }
+
+    fn floor(self) -> Result<Rational> {
+        Ok(Rational::from_integer(Rational::floor(&self)))
+    }
+
+    fn  acos(self)                -> Result<Rational> { unsupported("acos")  }
+    fn  asin(self)                -> Result<Rational> { unsupported("asin")  }
+    fn  atan(self)                -> Result<Rational> { unsupported("atan")  }
+    fn atan2(self, _: Rational)   -> Result<Rational> { unsupported("atan2") }
+    fn   cos(self)                -> Result<Rational> { unsupported("cos")   }
+    fn  cosh(self)                -> Result<Rational> { unsupported("cosh")  }
+    fn   exp(self)                -> Result<Rational> { unsupported("exp")   }
+    fn    ln(self)                -> Result<Rational> { unsupported("ln")    }
+    fn   log(self)                -> Result<Rational> { unsupported("log")   }
+    fn log10(self)                -> Result<Rational> { unsupported("log10") }
+    fn   sin(self)                -> Result<Rational> { unsupported("sin")   }
+    fn  sinh(self)                -> Result<Rational> { unsupported("sinh")  }
+    fn  sqrt(self)                -> Result<Rational> { unsupported("sqrt")  }
+    fn   tan(self)                -> Result<Rational> { unsupported("tan")   }
+    fn  tanh(self)                -> Result<Rational> { unsupported("tanh")  }
+
+    fn compare(&self, rhs: &Rational) -> Result<Option<Ordering>> {
+        // both denominators are positive
+        Ok(Some(self.numer.mul(&rhs.denom).cmp(&rhs.numer.mul(&self.denom))))
+    }
+
+    fn to_i64(&self) -> Result<Option<i64>> {
+        Ok(if self.is_integer() { self.numer.to_i64() } else { None })
+    }
+
+    fn isprime(self) -> Result<Rational> {
+        // trial division would not finish for large values, Miller-Rabin is exact below 2^63
+        let n = integral(self, "isprime")?;
+        match n.numer.to_i64() {
+            Some(n) => Ok(Rational::from_integer(BigInt::from_u64(number_theory::is_prime(n.max(0) as u64) as u64))),
+            None => Err(TinyExprError::Other(String::from("argument of `isprime` is too large"))),
+        }
+    }
+
+    fn   re(self) -> Result<Rational> { Ok(self) }
+    fn   im(self) -> Result<Rational> { Ok(Rational::default()) }
+    fn conj(self) -> Result<Rational> { Ok(self) }
+}
@@ -19,10 +19,32 @@ fn check_basics() {
     assert_eq!(tinyexpr::interp("5,2").unwrap(), 2.0);
 }
 
+#[test]
+fn check_number_literals() {
+    let syntax_error = |expr: &str| match tinyexpr::compile(expr, &["x"]) {
+        Err(tinyexpr::error::TinyExprError::Syntax(position, message)) => (position, message),
+        other => panic!("expected a syntax error, got {:?}", other),
+    };
+    assert_eq!(tinyexpr::interp(".5+5.").unwrap(), 5.5);
+    assert_eq!(tinyexpr::interp("007").unwrap(), 7.0);
+    assert_eq!(syntax_error("1.2.3"), (0, String::from("malformed number")));
+    assert_eq!(syntax_error("2 * 1..5"), (4, String::from("malformed number")));
+    assert_eq!(syntax_error("1e"), (0, String::from("malformed number")));
+    assert_eq!(syntax_error("x + 1e5"), (4, String::from("malformed number")));
+    assert_eq!(syntax_error("2E-3"), (0, String::from("malformed number")));
+    assert_eq!(syntax_error("."), (0, String::from("malformed number")));
+    assert_eq!(syntax_error("(.)"), (1, String::from("malformed number")));
+
+    // a name starting with `e` is still an implicit product
+    let options = tinyexpr::Options { implicit_multiplication: true, ..tinyexpr::Options::default() };
+    let expr = tinyexpr::compile_with("2exp(x)", &["x"], &options).unwrap().bind::<f64>().unwrap();
+    assert_eq!(expr.eval(&[0.0]).unwrap(), 2.0);
+}
+
 #[test]
 fn check_constants() {
-    assert_eq!(tinyexpr::interp("pi").unwrap(), 3.141592653589793);
-    assert_eq!(tinyexpr::interp("e").unwrap(), 2.718281828459045);
+    assert_eq!(tinyexpr::interp("pi").unwrap(), std::f64::consts::PI);
+    assert_eq!(tinyexpr::interp("e").unwrap(), std::f64::consts::E);
 }
 
 #[test]
@@ -51,6 +73,1022 @@ fn check_hyberbolic_trigs() {
     assert_eq!(tinyexpr::interp("tanh(10000)").unwrap(), 1.0);
 }
 
+#[test]
+fn check_combinatorics() {
+    assert_eq!(tinyexpr::interp("fac(0)").unwrap(), 1.0);
+    assert_eq!(tinyexpr::interp("fac(5)").unwrap(), 120.0);
+    assert_eq!(tinyexpr::interp("fac(20)").unwrap(), 2432902008176640000.0);
+    assert_eq!(tinyexpr::interp("fac(25)").unwrap(), 15511210043330985984000000.0);
+    assert_eq!(tinyexpr::interp("fac(170)").unwrap(), 7.257415615307999e306);
+    assert_eq!(tinyexpr::interp("fac(171)").unwrap(), f64::INFINITY);
+    assert!((tinyexpr::interp("fac(0.5)").unwrap() - std::f64::consts::PI.sqrt() / 2.0).abs() < 1e-14);
+    assert!(tinyexpr::interp("fac(-1)").unwrap().is_nan());
+
+    assert_eq!(tinyexpr::interp("ncr(5, 2)").unwrap(), 10.0);
+    assert_eq!(tinyexpr::interp("ncr(52, 5)").unwrap(), 2598960.0);
+    assert_eq!(tinyexpr::interp("ncr(100, 50)").unwrap(), 100891344545564193334812497256.0);
+    assert_eq!(tinyexpr::interp("ncr(10, 0)").unwrap(), 1.0);
+    assert_eq!(tinyexpr::interp("ncr(10, 10)").unwrap(), 1.0);
+    assert_eq!(tinyexpr::interp("ncr(2000, 1000)").unwrap(), f64::INFINITY);
+    assert!((tinyexpr::interp("ncr(4.5, 2)").unwrap() - 7.875).abs() < 1e-12);
+    assert!(tinyexpr::interp("ncr(-5, 2)").unwrap().is_nan());
+    assert!(tinyexpr::interp("ncr(2, 5)").unwrap().is_nan());
+
+    assert_eq!(tinyexpr::interp("npr(5, 2)").unwrap(), 20.0);
+    assert_eq!(tinyexpr::interp("npr(10, 10)").unwrap(), 3628800.0);
+    assert!((tinyexpr::interp("npr(4.5, 2)").unwrap() - 15.75).abs() < 1e-12);
+    assert!(tinyexpr::interp("npr(5, -1)").unwrap().is_nan());
+
+    assert_eq!(tinyexpr::interp_as::<f32>("fac(10)").unwrap(), 3628800.0f32);
+    assert!(tinyexpr::interp_as::<tinyexpr::Rational>("fac(3)").is_err());
+}
+
+#[test]
+fn check_special_functions() {
+    let close = |expr: &str, expected: f64| {
+        let r = tinyexpr::interp(expr).unwrap();
+        assert!(((r - expected) / expected).abs() < 1e-13, "{} = {}, expected {}", expr, r, expected);
+    };
+
+    close("erf(0.5)", 0.5204998778130465);
+    close("erf(1)", 0.8427007929497149);
+    close("erf(2)", 0.9953222650189527);
+    close("erf(-1)", -0.8427007929497149);
+    close("erfc(3)", 2.209049699858544e-05);
+    close("erfc(5)", 1.537459794428035e-12);
+    close("erfc(-1)", 1.8427007929497148);
+    assert_eq!(tinyexpr::interp("erf(0)").unwrap(), 0.0);
+
+    close("gamma(0.5)", 1.772453850905516);
+    close("gamma(4.5)", 11.63172839656745);
+    close("gamma(-0.5)", -3.544907701811032);
+    assert_eq!(tinyexpr::interp("gamma(5)").unwrap(), 24.0);
+    assert!(tinyexpr::interp("gamma(-2)").unwrap().is_nan());
+    close("lgamma(100)", 359.1342053695754);
+    close("lgamma(0.5)", 0.5723649429247001);
+    assert!(tinyexpr::interp("lgamma(2)").unwrap().abs() < 1e-15);
+
+    close("beta(2, 3)", 1.0 / 12.0);
+    close("beta(0.5, 0.5)", std::f64::consts::PI);
+    // computed through lgamma for large arguments, with lower accuracy
+    let r = tinyexpr::interp("beta(200, 300)").unwrap();
+    assert!((r / 1.6485491608664746e-147 - 1.0).abs() < 1e-11);
+
+    close("gammainc(2, 3)", 0.8008517265285442);
+    close("gammaincc(2, 3)", 0.1991482734714558);
+    close("gammainc(0.5, 4)", 0.9953222650189527);
+    assert_eq!(tinyexpr::interp("gammainc(2, 0)").unwrap(), 0.0);
+    assert!(tinyexpr::interp("gammainc(-1, 2)").unwrap().is_nan());
+
+    assert!(tinyexpr::interp_as::<tinyexpr::Rational>("erf(1)").is_err());
+}
+
+#[test]
+fn check_variadic_functions() {
+    assert_eq!(tinyexpr::interp("min(3)").unwrap(), 3.0);
+    assert_eq!(tinyexpr::interp("min(3, 1, 2)").unwrap(), 1.0);
+    assert_eq!(tinyexpr::interp("max(3, 1, 2, -5)").unwrap(), 3.0);
+    assert_eq!(tinyexpr::interp("max(1, 2) + min(1, 2)").unwrap(), 3.0);
+    assert!(tinyexpr::interp("max(1, 0/0, 2)").unwrap().is_nan());
+    assert_eq!(tinyexpr::interp("sum(1, 2, 3, 4, 5, 6, 7, 8, 9, 10)").unwrap(), 55.0);
+    assert_eq!(tinyexpr::interp("avg(1, 2, 3, 4)").unwrap(), 2.5);
+    assert_eq!(tinyexpr::interp("median(5, 1, 3)").unwrap(), 3.0);
+    assert_eq!(tinyexpr::interp("median(4, 1, 3, 2)").unwrap(), 2.5);
+    assert!((tinyexpr::interp("stddev(2, 4, 4, 4, 5, 5, 7, 9)").unwrap() - 2.138089935299395).abs() < 1e-14);
+    assert_eq!(tinyexpr::interp("sum(max(1, 2), (3, 4))").unwrap(), 6.0);
+
+    assert!(tinyexpr::interp("min()").is_err());
+    assert!(tinyexpr::interp("stddev(1)").is_err());
+    assert!(tinyexpr::interp("sum(1, 2").is_err());
+    assert!(tinyexpr::interp("pow(1, 2, 3)").is_err());
+    assert!(tinyexpr::interp("pow(1)").is_err());
+
+    let expr = tinyexpr::compile("max(x, y, 0)", &["x", "y"]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(expr.eval(&[-1.0, -2.0]).unwrap(), 0.0);
+    assert_eq!(expr.eval(&[1.0, 2.0]).unwrap(), 2.0);
+
+    let r = tinyexpr::interp_as::<tinyexpr::Rational>("median(1/3, 1/2, 1/4, 1)").unwrap();
+    assert_eq!(r, tinyexpr::Rational::new(5, 12));
+    assert_eq!(tinyexpr::interp_as::<tinyexpr::Rational>("avg(1, 2)").unwrap(), tinyexpr::Rational::new(3, 2));
+    assert!(tinyexpr::interp_as::<tinyexpr::Complex>("max(1, 2)").is_err());
+}
+
+#[test]
+fn check_rounding() {
+    assert_eq!(tinyexpr::interp("round(2.5)").unwrap(), 3.0);
+    assert_eq!(tinyexpr::interp("round(-2.5)").unwrap(), -3.0);
+    assert_eq!(tinyexpr::interp("round(2.4)").unwrap(), 2.0);
+    assert_eq!(tinyexpr::interp("round(1.23456, 3)").unwrap(), 1.235);
+    assert_eq!(tinyexpr::interp("round(2.675, 2)").unwrap(), 2.68);
+    assert_eq!(tinyexpr::interp("round(1234.5, -2)").unwrap(), 1200.0);
+    assert_eq!(tinyexpr::interp("round(10^300, 10)").unwrap(), 10f64.powf(300.0));
+    assert!(tinyexpr::interp("round(1.5, 0.5)").unwrap().is_nan());
+    assert!(tinyexpr::interp("round(1, 2, 3)").is_err());
+    assert!(tinyexpr::interp("round()").is_err());
+
+    assert_eq!(tinyexpr::interp("trunc(-2.7)").unwrap(), -2.0);
+    assert_eq!(tinyexpr::interp("trunc(2.7)").unwrap(), 2.0);
+    assert!((tinyexpr::interp("fract(-2.75)").unwrap() + 0.75).abs() < 1e-15);
+    assert_eq!(tinyexpr::interp("sign(-3)").unwrap(), -1.0);
+    assert_eq!(tinyexpr::interp("sign(0)").unwrap(), 0.0);
+    assert_eq!(tinyexpr::interp("sign(5)").unwrap(), 1.0);
+    assert_eq!(tinyexpr::interp("clamp(5, 0, 3)").unwrap(), 3.0);
+    assert_eq!(tinyexpr::interp("clamp(-5, 0, 3)").unwrap(), 0.0);
+    assert_eq!(tinyexpr::interp("clamp(2, 0, 3)").unwrap(), 2.0);
+    assert!(tinyexpr::interp("clamp(2, 3, 0)").is_err());
+    assert_eq!(tinyexpr::interp("roundto(17, 5)").unwrap(), 15.0);
+    assert_eq!(tinyexpr::interp("roundto(0.37, 0.25)").unwrap(), 0.25);
+    assert_eq!(tinyexpr::interp("mod(-7, 3)").unwrap(), 2.0);
+    assert_eq!(tinyexpr::interp("mod(7, -3)").unwrap(), 1.0);
+    assert_eq!(tinyexpr::interp("-7 % 3").unwrap(), -1.0);
+
+    use tinyexpr::decimal::{Decimal, Down, HalfUp};
+    use tinyexpr::Rational;
+    assert_eq!(tinyexpr::interp_as::<Decimal<4>>("round(2.345, 2)").unwrap().to_string(), "2.3400");
+    assert_eq!(tinyexpr::interp_as::<Decimal<4, HalfUp>>("round(2.345, 2)").unwrap().to_string(), "2.3500");
+    assert_eq!(tinyexpr::interp_as::<Decimal<4, Down>>("round(2.349, 2)").unwrap().to_string(), "2.3400");
+    assert_eq!(tinyexpr::interp_as::<Decimal<2>>("round(2.5)").unwrap().to_string(), "2.00");
+    assert_eq!(tinyexpr::interp_as::<Decimal<2>>("round(3.5)").unwrap().to_string(), "4.00");
+    assert_eq!(tinyexpr::interp_as::<Decimal<2>>("round(-2.5)").unwrap().to_string(), "-2.00");
+    assert_eq!(tinyexpr::interp_as::<Decimal<2, HalfUp>>("round(-2.5)").unwrap().to_string(), "-3.00");
+    assert_eq!(tinyexpr::interp_as::<Decimal<2, Down>>("round(2.99)").unwrap().to_string(), "2.00");
+    assert_eq!(tinyexpr::interp_as::<Decimal<2>>("round(1250, -2)").unwrap().to_string(), "1200.00");
+    assert_eq!(tinyexpr::interp_as::<Decimal<2>>("round(1.25, 5)").unwrap().to_string(), "1.25");
+    assert_eq!(tinyexpr::interp_as::<Decimal<2>>("round(1250, -100000)").unwrap().to_string(), "0.00");
+    assert_eq!(tinyexpr::interp_as::<Decimal<2>>("roundto(10.375, 0.05)").unwrap().to_string(), "10.40");
+    assert_eq!(tinyexpr::interp_as::<Decimal<2, Down>>("roundto(10.39, 0.05)").unwrap().to_string(), "10.35");
+    assert!(tinyexpr::interp_as::<Decimal<2>>("round(1.25, 0.5)").is_err());
+    assert!(tinyexpr::interp_as::<Decimal<2>>("roundto(1.25, 0)").is_err());
+    assert_eq!(tinyexpr::interp_as::<Decimal<0, HalfUp>>("round(7/2)").unwrap().to_string(), "4");
+    assert_eq!(tinyexpr::interp_as::<Decimal<2>>("roundto(10.37, 0.05)").unwrap().to_string(), "10.35");
+    assert_eq!(tinyexpr::interp_as::<Rational>("round(-5/2)").unwrap(), Rational::new(-3, 1));
+    assert_eq!(tinyexpr::interp_as::<Rational>("fract(7/3)").unwrap(), Rational::new(1, 3));
+    assert_eq!(tinyexpr::interp_as::<Rational>("mod(-7/2, 2)").unwrap(), Rational::new(1, 2));
+    assert_eq!(tinyexpr::interp_as::<Rational>("round(1/3, 2)").unwrap(), Rational::new(33, 100));
+    assert_eq!(tinyexpr::interp_as::<tinyexpr::Interval>("round(2.4)").unwrap(), tinyexpr::Interval::point(2.0));
+}
+
+#[test]
+fn check_more_elementary_functions() {
+    let close = |expr: &str, expected: f64| {
+        let r = tinyexpr::interp(expr).unwrap();
+        assert!((r - expected).abs() <= 1e-15 * expected.abs().max(1.0), "{} = {}, expected {}", expr, r, expected);
+    };
+
+    close("cbrt(27)", 3.0);
+    close("cbrt(-8)", -2.0);
+    close("hypot(3, 4)", 5.0);
+    close("log2(1024)", 10.0);
+    close("log1p(0.00000001)", 9.9999995e-9);
+    close("expm1(0.00000001)", 1.000000005e-8);
+    close("asinh(1)", 0.881373587019543);
+    close("acosh(2)", 1.3169578969248166);
+    close("atanh(0.5)", 0.5493061443340549);
+    close("sec(0)", 1.0);
+    close("csc(pi/2)", 1.0);
+    close("cot(pi/4)", 1.0);
+    assert!(tinyexpr::interp("acosh(0.5)").unwrap().is_nan());
+    assert_eq!(tinyexpr::interp("atanh(1)").unwrap(), f64::INFINITY);
+
+    use tinyexpr::Complex;
+    let z = tinyexpr::interp_as::<Complex>("acosh(0.5)").unwrap();
+    assert!(z.re.abs() < 1e-15 && (z.im - std::f64::consts::FRAC_PI_3).abs() < 1e-15);
+    let z = tinyexpr::interp_as::<Complex>("cbrt(-8)").unwrap();
+    assert!((z.re - 1.0).abs() < 1e-15 && (z.im - 3f64.sqrt()).abs() < 1e-15);
+
+    let hyp = tinyexpr::compile("hypot(x, 4)", &["x"]).unwrap().bind::<tinyexpr::Interval>().unwrap();
+    let r = hyp.eval(&[tinyexpr::Interval::new(-3.0, 3.0)]).unwrap();
+    assert!(r.contains(4.0) && r.contains(5.0));
+}
+
+#[test]
+fn check_angle_units() {
+    use tinyexpr::{AngleUnit, Options};
+    let degrees = Options { angle_unit: AngleUnit::Degrees, ..Options::default() };
+    let eval = |expr: &str, options: &Options| {
+        tinyexpr::compile_with(expr, &[], options).unwrap().bind::<f64>().unwrap().eval(&[]).unwrap()
+    };
+
+    assert!((eval("sin(30)", &degrees) - 0.5).abs() < 1e-15);
+    assert!((eval("cos 60", &degrees) - 0.5).abs() < 1e-15);
+    assert!((eval("tan(45)", &degrees) - 1.0).abs() < 1e-15);
+    assert!((eval("cot(45)", &degrees) - 1.0).abs() < 1e-15);
+    assert!((eval("asin(0.5)", &degrees) - 30.0).abs() < 1e-13);
+    assert!((eval("acos(0)", &degrees) - 90.0).abs() < 1e-13);
+    assert!((eval("atan2(1, 1)", &degrees) - 45.0).abs() < 1e-13);
+    assert!((eval("sin(asin(0.25))", &degrees) - 0.25).abs() < 1e-15);
+    assert!((eval("sinh(1)", &degrees) - 1f64.sinh()).abs() < 1e-15);
+    assert!((eval("(sin(30))", &degrees) - 0.5).abs() < 1e-15);
+    assert!((eval("((cos(60)))", &degrees) - 0.5).abs() < 1e-15);
+    let implicit = Options { implicit_multiplication: true, ..degrees.clone() };
+    assert!((eval("2(tan(45))", &implicit) - 2.0).abs() < 1e-15);
+
+    assert_eq!(eval("deg(pi)", &degrees), 180.0);
+    assert_eq!(eval("deg(pi)", &Options::default()), 180.0);
+    assert_eq!(eval("rad(180)", &degrees), std::f64::consts::PI);
+    assert!((eval("sin(rad(90))", &Options::default()) - 1.0).abs() < 1e-15);
+    assert!((tinyexpr::interp("sin(pi/6)").unwrap() - 0.5).abs() < 1e-15);
+
+    let expr = tinyexpr::compile_with("sin(x)^2 + cos(x)^2", &["x"], &degrees).unwrap().bind::<f64>().unwrap();
+    assert!((expr.eval(&[123.0]).unwrap() - 1.0).abs() < 1e-15);
+}
+
+#[test]
+fn check_distributions() {
+    let close = |expr: &str, expected: f64| {
+        let r = tinyexpr::interp(expr).unwrap();
+        assert!(((r - expected) / expected).abs() < 1e-13, "{} = {}, expected {}", expr, r, expected);
+    };
+
+    close("normpdf(0, 0, 1)", 0.3989422804014327);
+    close("normcdf(1.96, 0, 1)", 0.9750021048517795);
+    close("normcdf(70, 100, 15)", 0.02275013194817921);
+    close("norminv(0.975, 0, 1)", 1.959963984540054);
+    close("norminv(10^-10, 0, 1)", -6.361340902404056);
+    close("lognpdf(1, 0, 1)", 0.3989422804014327);
+    close("logncdf(e, 0, 1)", 0.8413447460685429);
+    close("logninv(0.5, 2, 1)", std::f64::consts::E * std::f64::consts::E);
+
+    close("binompdf(3, 10, 0.5)", 0.1171875);
+    close("binomcdf(3, 10, 0.5)", 0.171875);
+    assert_eq!(tinyexpr::interp("binominv(0.5, 10, 0.5)").unwrap(), 5.0);
+    assert_eq!(tinyexpr::interp("binompdf(2.5, 10, 0.5)").unwrap(), 0.0);
+    assert!(tinyexpr::interp("binompdf(3, 10, 1.5)").unwrap().is_nan());
+    close("poisspdf(2, 3)", 4.5 * (-3f64).exp());
+    close("poisscdf(2, 3)", 8.5 * (-3f64).exp());
+    assert_eq!(tinyexpr::interp("poissinv(0.5, 3)").unwrap(), 3.0);
+    assert!(tinyexpr::interp("poissinv(0.5, 1/0)").unwrap().is_nan());
+    assert!(tinyexpr::interp("poissinv(0.5, 10^300)").unwrap().is_nan());
+    assert!((tinyexpr::interp("poissinv(0.5, 1000000000000)").unwrap() - 1e12).abs() <= 1.0);
+    close("exppdf(1, 2)", 2.0 * (-2f64).exp());
+    close("expcdf(1, 2)", 1.0 - (-2f64).exp());
+    close("expinv(0.5, 2)", std::f64::consts::LN_2 / 2.0);
+
+    close("tpdf(0, 1)", std::f64::consts::FRAC_1_PI);
+    close("tcdf(-2, 3)", 0.06966298427942158);
+    close("tinv(0.975, 10)", 2.228138851986275);
+    assert!(tinyexpr::interp("tinv(0.5, 10)").unwrap().abs() < 1e-15);
+    assert!(tinyexpr::interp("normpdf(0, 0, -1)").unwrap().is_nan());
+
+    let var = tinyexpr::compile("mu - norminv(0.99, 0, 1) * sigma", &["mu", "sigma"]).unwrap().bind::<f64>().unwrap();
+    assert!((var.eval(&[0.05, 0.2]).unwrap() + 0.4152695748081682).abs() < 1e-14);
+    assert!(tinyexpr::interp_as::<tinyexpr::Rational>("normcdf(0, 0, 1)").is_err());
+}
+
+#[test]
+fn check_number_theory() {
+    assert_eq!(tinyexpr::interp("gcd(12, 18)").unwrap(), 6.0);
+    assert_eq!(tinyexpr::interp("gcd(-12, 18)").unwrap(), 6.0);
+    assert_eq!(tinyexpr::interp("gcd(0, 0)").unwrap(), 0.0);
+    assert_eq!(tinyexpr::interp("lcm(4, -6)").unwrap(), 12.0);
+    assert_eq!(tinyexpr::interp("lcm(0, 5)").unwrap(), 0.0);
+    assert_eq!(tinyexpr::interp("idiv(7, 2)").unwrap(), 3.0);
+    assert_eq!(tinyexpr::interp("idiv(-7, 2)").unwrap(), -4.0);
+    assert_eq!(tinyexpr::interp("idiv(-7, 2)*2 + mod(-7, 2)").unwrap(), -7.0);
+    assert!(tinyexpr::interp("idiv(7, 0)").unwrap().is_nan());
+
+    assert_eq!(tinyexpr::interp("isprime(2)").unwrap(), 1.0);
+    assert_eq!(tinyexpr::interp("isprime(1)").unwrap(), 0.0);
+    assert_eq!(tinyexpr::interp("isprime(-7)").unwrap(), 0.0);
+    assert_eq!(tinyexpr::interp("isprime(91)").unwrap(), 0.0);
+    assert_eq!(tinyexpr::interp("isprime(2^31-1)").unwrap(), 1.0);
+    assert_eq!(tinyexpr::interp("isprime(2^53-111)").unwrap(), 1.0);
+    assert_eq!(tinyexpr::interp("isprime(3215031751)").unwrap(), 0.0); // strong pseudoprime to bases 2, 3, 5 and 7
+
+    assert_eq!(tinyexpr::interp("powmod(2, 10, 1000)").unwrap(), 24.0);
+    assert_eq!(tinyexpr::interp("powmod(3, 200, 1000000007)").unwrap(), 136318165.0);
+    assert_eq!(tinyexpr::interp("powmod(2, 10^30, 7)").unwrap(), 2.0);
+    assert_eq!(tinyexpr::interp("powmod(-2, 3, 5)").unwrap(), 2.0);
+    assert_eq!(tinyexpr::interp("powmod(5, 0, 1)").unwrap(), 0.0);
+
+    for expr in &["gcd(2.5, 5)", "lcm(4, 0.5)", "idiv(7.5, 2)", "isprime(7.1)", "powmod(2, 3, 5.5)", "powmod(2, -1, 5)"] {
+        assert!(tinyexpr::interp(expr).is_err(), "{} should fail", expr);
+    }
+
+    use tinyexpr::Rational;
+    assert_eq!(tinyexpr::interp_as::<Rational>("powmod(3, 200, 1000000007)").unwrap(), Rational::new(136318165, 1));
+    assert_eq!(tinyexpr::interp_as::<Rational>("isprime(2^61-1)").unwrap(), Rational::new(1, 1));
+    assert_eq!(tinyexpr::interp_as::<Rational>("lcm(21, 6)").unwrap(), Rational::new(42, 1));
+    assert!(tinyexpr::interp_as::<Rational>("gcd(1/2, 1)").is_err());
+    assert_eq!(tinyexpr::interp_as::<tinyexpr::Decimal<2>>("idiv(10, 3)").unwrap().to_string(), "3.00");
+}
+
+#[test]
+fn check_random() {
+    let uniform = tinyexpr::compile("rand()", &[]).unwrap().bind::<f64>().unwrap();
+    let dice = tinyexpr::compile("randint(1, 2*3)", &[]).unwrap().bind::<f64>().unwrap();
+    let normal = tinyexpr::compile("randn(mu, 2)", &["mu"]).unwrap().bind::<f64>().unwrap();
+    // impure functions are never folded, but their constant arguments are
+    assert_ne!(uniform.e_type, tinyexpr::ExprType::TE_CONSTANT);
+    assert_ne!(dice.e_type, tinyexpr::ExprType::TE_CONSTANT);
+    assert_eq!(dice.parameters[1].e_type, tinyexpr::ExprType::TE_CONSTANT);
+
+    tinyexpr::seed_random(7);
+    let first: Vec<f64> = (0..10).map(|_| uniform.eval(&[]).unwrap()).collect();
+    tinyexpr::seed_random(7);
+    let again: Vec<f64> = (0..10).map(|_| uniform.eval(&[]).unwrap()).collect();
+    assert_eq!(first, again);
+    assert!(first.windows(2).any(|w| w[0] != w[1]));
+
+    let (mut sum, mut squares) = (0.0, 0.0);
+    let mut faces = [0; 6];
+    for _ in 0..10000 {
+        let u = uniform.eval(&[]).unwrap();
+        assert!((0.0..1.0).contains(&u));
+        let d = dice.eval(&[]).unwrap();
+        assert!(d.fract() == 0.0 && (1.0..=6.0).contains(&d));
+        faces[d as usize - 1] += 1;
+        let z = normal.eval(&[10.0]).unwrap() - 10.0;
+        sum += z;
+        squares += z * z;
+    }
+    assert!(faces.iter().all(|&n| n > 1400 && n < 1950));
+    assert!((sum / 10000.0).abs() < 0.1);
+    assert!((squares / 10000.0 - 4.0).abs() < 0.3);
+
+    assert_eq!(tinyexpr::interp("randint(5, 5)").unwrap(), 5.0);
+    assert!(tinyexpr::interp("randint(1.5, 3)").is_err());
+    assert!(tinyexpr::interp("randint(3, 1)").is_err());
+    assert!(tinyexpr::interp("rand(1)").is_err());
+    assert!(tinyexpr::interp_as::<f32>("rand").unwrap() < 1.0);
+    let r = tinyexpr::interp_as::<tinyexpr::Rational>("randint(-3, 3)").unwrap();
+    assert!(r.is_integer() && r.to_f64().abs() <= 3.0);
+
+    // negative deviates are drawn for exact types too
+    tinyexpr::seed_random(11);
+    let exact = tinyexpr::compile("randn(0, 1)", &[]).unwrap();
+    let rational = exact.bind::<tinyexpr::Rational>().unwrap();
+    let decimal = exact.bind::<tinyexpr::Decimal<2>>().unwrap();
+    let (mut negative, mut cents) = (0, 0);
+    for _ in 0..200 {
+        if rational.eval(&[]).unwrap().to_f64() < 0.0 {
+            negative += 1;
+        }
+        if decimal.eval(&[]).unwrap().to_string().starts_with('-') {
+            cents += 1;
+        }
+    }
+    assert!(negative > 50 && cents > 50);
+}
+
+#[test]
+fn check_bitwise() {
+    assert_eq!(tinyexpr::interp("6 & 3").unwrap(), 2.0);
+    assert_eq!(tinyexpr::interp("6 | 3").unwrap(), 7.0);
+    assert_eq!(tinyexpr::interp("6 xor 3").unwrap(), 5.0);
+    assert_eq!(tinyexpr::interp("~0").unwrap(), -1.0);
+    assert_eq!(tinyexpr::interp("-~5").unwrap(), 6.0);
+    assert_eq!(tinyexpr::interp("~~7").unwrap(), 7.0);
+    assert_eq!(tinyexpr::interp("1 << 4").unwrap(), 16.0);
+    assert_eq!(tinyexpr::interp("-16 >> 2").unwrap(), -4.0);
+    assert_eq!(tinyexpr::interp("-(2^63) >> 63").unwrap(), -1.0);
+
+    // shifts bind less tightly than arithmetic, then come `&`, `xor` and `|`
+    assert_eq!(tinyexpr::interp("1 + 2 << 3").unwrap(), 24.0);
+    assert_eq!(tinyexpr::interp("1 | 2 & 3").unwrap(), 3.0);
+    assert_eq!(tinyexpr::interp("1 xor 3 & 1").unwrap(), 0.0);
+    assert_eq!(tinyexpr::interp("5 | 8 xor 12").unwrap(), 5.0);
+    assert_eq!(tinyexpr::interp("max(6 & 3, 1)").unwrap(), 2.0);
+
+    let reg = tinyexpr::compile("(reg >> 4) & 15 | 1 << 8", &["reg"]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(reg.eval(&[0xabcd as f64]).unwrap(), 0x10c as f64);
+
+    for expr in &["2.5 & 1", "2^63 | 1", "1 << 63", "1 << 64", "1 >> -1", "1 < < 2", "3 xor", "~"] {
+        assert!(tinyexpr::interp(expr).is_err(), "{} should fail", expr);
+    }
+
+    use tinyexpr::Rational;
+    assert_eq!(tinyexpr::interp_as::<Rational>("(2^40 + 5) & 7").unwrap(), Rational::new(5, 1));
+    assert!(tinyexpr::interp_as::<Rational>("1/2 | 1").is_err());
+    assert_eq!(tinyexpr::interp_as::<tinyexpr::Decimal<2>>("~3").unwrap().to_string(), "-4.00");
+    assert!(tinyexpr::interp_as::<tinyexpr::Complex>("1 & 1").is_err());
+}
+
+#[test]
+fn check_implicit_multiplication() {
+    use tinyexpr::Options;
+    let implicit = Options { implicit_multiplication: true, ..Options::default() };
+    let eval = |expr: &str| {
+        tinyexpr::compile_with(expr, &["x", "y"], &implicit).unwrap().bind::<f64>().unwrap().eval(&[3.0, 2.0]).unwrap()
+    };
+
+    assert_eq!(eval("2x"), 6.0);
+    assert_eq!(eval("3(x+y)"), 15.0);
+    assert_eq!(eval("(x+y)(x-y)"), 5.0);
+    assert_eq!(eval("2pi"), 2.0 * std::f64::consts::PI);
+    assert_eq!(eval("x y"), 6.0);
+    assert_eq!(eval("x(y)"), 6.0);
+    assert_eq!(eval("2(3)(4)"), 24.0);
+    assert_eq!(eval("pi()"), std::f64::consts::PI);
+    assert_eq!(eval("pi(2)"), 2.0 * std::f64::consts::PI);
+
+    // same precedence as `*`, with functions taking a single operand without parentheses
+    assert_eq!(eval("1/2x"), 1.5);
+    assert_eq!(eval("2x^2"), 18.0);
+    assert_eq!(eval("-2x"), -6.0);
+    assert_eq!(eval("2 -x"), -1.0);
+    assert_eq!(eval("sin x"), 3f64.sin());
+    assert_eq!(eval("2sin x cos x"), 2.0 * 3f64.sin() * 3f64.cos());
+    assert_eq!(eval("sin 2x"), 2f64.sin() * 3.0);
+    assert_eq!(eval("max(1, 2)(3)"), 6.0);
+
+    // names are never split, and the default mode rejects juxtaposition
+    assert!(tinyexpr::compile_with("xy", &["x", "y"], &implicit).is_err());
+
+    // numbers are not multiplied with each other
+    let syntax_error = |expr: &str| match tinyexpr::compile_with(expr, &["x", "y"], &implicit) {
+        Err(tinyexpr::error::TinyExprError::Syntax(position, message)) => (position, message),
+        other => panic!("expected a syntax error, got {:?}", other.map(|_| ())),
+    };
+    assert_eq!(syntax_error("2 3"), (2, String::from("missing operator between numbers")));
+    assert_eq!(syntax_error("1 000"), (2, String::from("missing operator between numbers")));
+    assert_eq!(syntax_error("x + 2^3 4"), (8, String::from("missing operator between numbers")));
+    assert_eq!(eval("2(3)"), 6.0);
+    assert_eq!(eval("2 x 3"), 18.0);
+    for expr in &["2x", "3(x+y)", "(x+y)(x-y)", "2pi", "pi(2)"] {
+        assert!(tinyexpr::compile(expr, &["x", "y"]).is_err(), "{} should fail", expr);
+    }
+}
+
+#[test]
+fn check_postfix_operators() {
+    assert_eq!(tinyexpr::interp("5!").unwrap(), 120.0);
+    assert_eq!(tinyexpr::interp("3!!").unwrap(), 720.0);
+    assert_eq!(tinyexpr::interp("-3!").unwrap(), -6.0);
+    assert_eq!(tinyexpr::interp("2^3!").unwrap(), 64.0);
+    assert_eq!(tinyexpr::interp("(1+2)! / 2").unwrap(), 3.0);
+    assert!(tinyexpr::interp("!5").is_err());
+
+    // percent is opt-in, `%` stays the remainder by default
+    assert!(tinyexpr::interp("50%").is_err());
+    assert_eq!(tinyexpr::interp("7 % -3").unwrap(), 1.0);
+
+    use tinyexpr::{Options, Percent};
+    let percent = Options { percent: Percent::Hundredth, ..Options::default() };
+    let eval = |expr: &str| {
+        tinyexpr::compile_with(expr, &["x"], &percent).unwrap().bind::<f64>().unwrap().eval(&[3.0]).unwrap()
+    };
+    assert_eq!(eval("50%"), 0.5);
+    assert_eq!(eval("200 * 10%"), 20.0);
+    assert_eq!(eval("50% - 10"), -9.5);
+    assert_eq!(eval("x%"), 0.03);
+    assert_eq!(eval("max(10%, 1)"), 1.0);
+    assert_eq!(eval("5!%"), 1.2);
+    assert_eq!(eval("7 % 3"), 1.0);
+    assert_eq!(eval("7%x"), 1.0);
+    assert_eq!(eval("7 % (-3)"), 1.0);
+
+    let price = tinyexpr::compile_with("x * (1 + 8%)", &["x"], &percent).unwrap().bind::<tinyexpr::Rational>().unwrap();
+    assert_eq!(price.eval(&[tinyexpr::Rational::new(25, 1)]).unwrap(), tinyexpr::Rational::new(27, 1));
+}
+
+#[test]
+fn check_absolute_value_bars() {
+    assert_eq!(tinyexpr::interp("|-3|").unwrap(), 3.0);
+    assert_eq!(tinyexpr::interp("|2 - 5| * 2").unwrap(), 6.0);
+    assert_eq!(tinyexpr::interp("-|-2|").unwrap(), -2.0);
+    assert_eq!(tinyexpr::interp("|1 - |2 - 7||").unwrap(), 4.0);
+    assert_eq!(tinyexpr::interp("max(|-1|, |-2|)").unwrap(), 2.0);
+    // inside bars, bitwise or needs parentheses
+    assert_eq!(tinyexpr::interp("|(1 | 2)|").unwrap(), 3.0);
+    assert_eq!(tinyexpr::interp("1 | 2").unwrap(), 3.0);
+
+    let expr = tinyexpr::compile("||a|-|b||", &["a", "b"]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(expr.eval(&[1.0, -4.0]).unwrap(), 3.0);
+    assert_eq!(expr.eval(&[-5.0, 2.0]).unwrap(), 3.0);
+
+    let syntax_error = |expr: &str| match tinyexpr::interp(expr) {
+        Err(tinyexpr::error::TinyExprError::Syntax(position, message)) => (position, message),
+        other => panic!("expected a syntax error, got {:?}", other),
+    };
+    assert_eq!(syntax_error("|3"), (0, String::from("`|` is not closed")));
+    assert_eq!(syntax_error("2 * (|3)"), (5, String::from("`|` is not closed")));
+    assert_eq!(syntax_error("|1|2|"), (2, String::from("ambiguous `|`")));
+    assert_eq!(syntax_error("|1|2|3|"), (2, String::from("ambiguous `|`")));
+    assert_eq!(syntax_error("|1 - |2|3|"), (7, String::from("ambiguous `|`")));
+    assert_eq!(syntax_error("1 |"), (3, String::from("unexpected end of expression")));
+    assert_eq!(tinyexpr::interp("|3").unwrap_err().to_string(), "`|` is not closed at position 0");
+
+    // with implicit multiplication, a bar after an operand that is closed later opens an absolute value
+    let options = tinyexpr::Options { implicit_multiplication: true, ..tinyexpr::Options::default() };
+    let eval = |e: &str| tinyexpr::compile_with(e, &["x", "y"], &options).unwrap().bind::<f64>().unwrap().eval(&[-3.0, 5.0]).unwrap();
+    assert_eq!(eval("2|x|"), 6.0);
+    assert_eq!(eval("2|x| + 1"), 7.0);
+    assert_eq!(eval("|x||y - 7|"), 6.0);
+    assert_eq!(eval("y|x - 1|^2"), 80.0);
+    assert_eq!(eval("(y)|x|"), 15.0);
+    // within bars such a bar closes them
+    assert_eq!(eval("|2(|x|)|"), 6.0);
+    assert_eq!(eval("1 | 2"), 3.0);
+    assert_eq!(eval("y | 2"), 7.0);
+    assert!(tinyexpr::compile("2|x|", &["x"]).is_err());
+
+    let syntax_error = |e: &str| match tinyexpr::compile_with(e, &["x", "y"], &options) {
+        Err(tinyexpr::error::TinyExprError::Syntax(position, message)) => (position, message),
+        other => panic!("expected a syntax error, got {:?}", other.map(|_| ())),
+    };
+    assert_eq!(syntax_error("|x|y|"), (4, String::from("`|` is not closed")));
+    assert_eq!(eval("(x|y|)"), -15.0);
+    assert_eq!(syntax_error("(x|)"), (2, String::from("`|` is not closed")));
+    assert_eq!(syntax_error("|3"), (0, String::from("`|` is not closed")));
+}
+
+#[test]
+fn check_unicode_symbols() {
+    assert_eq!(tinyexpr::interp("π").unwrap(), std::f64::consts::PI);
+    assert!(tinyexpr::interp("2π").is_err());
+    assert_eq!(tinyexpr::interp("6 × 7").unwrap(), 42.0);
+    assert_eq!(tinyexpr::interp("7 ÷ 2").unwrap(), 3.5);
+    assert_eq!(tinyexpr::interp("5 − 8").unwrap(), -3.0);
+    assert_eq!(tinyexpr::interp("−2").unwrap(), -2.0);
+    assert_eq!(tinyexpr::interp("√16").unwrap(), 4.0);
+    assert_eq!(tinyexpr::interp("√(3² + 4²)").unwrap(), 5.0);
+    assert_eq!(tinyexpr::interp("-3²").unwrap(), -9.0);
+    assert_eq!(tinyexpr::interp("2 × π ÷ π").unwrap(), 2.0);
+    assert_eq!(tinyexpr::interp("1\u{a0}+\u{a0}1").unwrap(), 2.0);
+
+    // the plain numbers and names at the end of the input are read completely
+    assert_eq!(tinyexpr::interp("1.").unwrap(), 1.0);
+    assert!(tinyexpr::interp("log10").is_err());
+    let expr = tinyexpr::compile("x1 ≤ x2", &["x1", "x2"]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(expr.eval(&[1.0, 2.0]).unwrap(), 1.0);
+    assert_eq!(expr.eval(&[2.0, 2.0]).unwrap(), 1.0);
+    assert_eq!(expr.eval(&[3.0, 2.0]).unwrap(), 0.0);
+
+    // errors point at the byte where the unexpected symbol starts
+    match tinyexpr::interp("π × €") {
+        Err(tinyexpr::error::TinyExprError::Syntax(position, _)) => assert_eq!(position, 6),
+        other => panic!("expected a syntax error, got {:?}", other),
+    }
+}
+
+#[test]
+fn check_conditional() {
+    assert_eq!(tinyexpr::interp("if(1, 2, 3)").unwrap(), 2.0);
+    assert_eq!(tinyexpr::interp("if(0, 2, 3)").unwrap(), 3.0);
+    assert_eq!(tinyexpr::interp("if(-0.5, 2, 3)").unwrap(), 2.0);
+    let expr = tinyexpr::compile("if(x > 0, sqrt(x), -1)", &["x"]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(expr.eval(&[4.0]).unwrap(), 2.0);
+    assert_eq!(expr.eval(&[-4.0]).unwrap(), -1.0);
+    // the other branch is not evaluated
+    let expr = tinyexpr::compile("if(x == 0, 0, 1/x)", &["x"]).unwrap().bind::<tinyexpr::Rational>().unwrap();
+    assert_eq!(expr.eval(&[tinyexpr::Rational::new(0, 1)]).unwrap(), tinyexpr::Rational::new(0, 1));
+    assert!(tinyexpr::interp("if(1, 2)").is_err());
+
+    // branches are not folded ahead, except the one chosen by a constant condition
+    let script = tinyexpr::compile_script("if(x, 1, 1/0)", &["x"]).unwrap().bind::<tinyexpr::Rational>().unwrap();
+    assert_eq!(script.eval(&[tinyexpr::Rational::new(2, 1)]).unwrap(), tinyexpr::Rational::new(1, 1));
+    assert_eq!(script.eval(&[tinyexpr::Rational::new(0, 1)]).unwrap_err().to_string(), "division by zero");
+    let expr = tinyexpr::compile("if(2 > 1, x + 1, 1/0)", &["x"]).unwrap().bind::<tinyexpr::Rational>().unwrap();
+    assert_eq!(expr.e_type, tinyexpr::ExprType::TE_FUNCTION2 | tinyexpr::ExprType::TE_FLAG_PURE);
+    assert_eq!(expr.eval(&[tinyexpr::Rational::new(1, 2)]).unwrap(), tinyexpr::Rational::new(3, 2));
+    assert_eq!(tinyexpr::interp_as::<tinyexpr::Rational>("if(0, 1/0, 2 + 3)").unwrap(), tinyexpr::Rational::new(5, 1));
+    assert!(tinyexpr::interp_as::<tinyexpr::Rational>("if(1, 1/0, 2)").is_err());
+
+    // the condition needs comparisons
+    let interval = tinyexpr::compile("if(x, 1, 2)", &["x"]).unwrap().bind::<tinyexpr::Interval>().unwrap();
+    assert_eq!(interval.eval(&[tinyexpr::Interval::point(1.0)]).unwrap_err().to_string(), "`if` is not supported by this number type");
+    assert_eq!(tinyexpr::interp_as::<tinyexpr::Complex>("if(i, 1, 2)").unwrap_err().to_string(), "`if` is not supported by this number type");
+}
+
+#[test]
+fn check_comparisons() {
+    assert_eq!(tinyexpr::interp("1 < 2").unwrap(), 1.0);
+    assert_eq!(tinyexpr::interp("2 <= 1").unwrap(), 0.0);
+    assert_eq!(tinyexpr::interp("2 > 1").unwrap(), 1.0);
+    assert_eq!(tinyexpr::interp("1 >= 1").unwrap(), 1.0);
+    assert_eq!(tinyexpr::interp("1 + 1 == 2").unwrap(), 1.0);
+    assert_eq!(tinyexpr::interp("1 != 1").unwrap(), 0.0);
+    assert_eq!(tinyexpr::interp("2 ≥ 3").unwrap(), 0.0);
+    assert_eq!(tinyexpr::interp("2 ≠ 3").unwrap(), 1.0);
+    assert_eq!(tinyexpr::interp("1 << 2 < 5").unwrap(), 1.0);
+    assert_eq!(tinyexpr::interp("max(1 < 2, 0)").unwrap(), 1.0);
+    assert_eq!(tinyexpr::interp("3! == 6").unwrap(), 1.0);
+    assert_eq!(tinyexpr::interp("sqrt(-1) == sqrt(-1)").unwrap(), 0.0);
+    assert_eq!(tinyexpr::interp("sqrt(-1) != sqrt(-1)").unwrap(), 1.0);
+    assert!(tinyexpr::interp("1 = 1").is_err());
+    assert_eq!(tinyexpr::interp_as::<tinyexpr::Rational>("1/3 < 0.34").unwrap(), tinyexpr::Rational::new(1, 1));
+}
+
+#[test]
+fn check_identifiers() {
+    let expr = tinyexpr::compile("Vmax * flow_rate + order.total - _x2", &["Vmax", "flow_rate", "order.total", "_x2"])
+        .unwrap().bind::<f64>().unwrap();
+    assert_eq!(expr.eval(&[2.0, 3.0, 4.0, 1.0]).unwrap(), 9.0);
+
+    // a path is a single name, unknown paths are not split at the dots
+    let expr = tinyexpr::compile("a.b.c + a.b", &["a.b.c", "a.b"]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(expr.eval(&[1.0, 2.0]).unwrap(), 3.0);
+    assert!(tinyexpr::compile("order.total", &["order"]).is_err());
+    assert!(tinyexpr::compile("order.", &["order"]).is_err());
+    assert!(tinyexpr::compile("order.2", &["order"]).is_err());
+
+    // names are case sensitive and builtins lowercase by default
+    assert!(tinyexpr::compile("X", &["x"]).is_err());
+    assert!(tinyexpr::interp("SQRT(4)").is_err());
+    assert!(tinyexpr::interp("Pi").is_err());
+
+    use tinyexpr::Options;
+    let options = Options { case_insensitive: true, ..Options::default() };
+    let expr = tinyexpr::compile_with("SQRT(Rate) * PI + Order.Total", &["rate", "order.total"], &options).unwrap().bind::<f64>().unwrap();
+    assert_eq!(expr.eval(&[4.0, 1.0]).unwrap(), 2.0 * std::f64::consts::PI + 1.0);
+    assert_eq!(tinyexpr::compile_with("6 XOR 3", &[], &options).unwrap().bind::<f64>().unwrap().eval(&[]).unwrap(), 5.0);
+    // an exact match wins over the other cases
+    let expr = tinyexpr::compile_with("v - V", &["V", "v"], &options).unwrap().bind::<f64>().unwrap();
+    assert_eq!(expr.eval(&[3.0, 1.0]).unwrap(), -2.0);
+}
+
+#[test]
+fn check_quoted_identifiers() {
+    let vars = ["Gross Margin %", "Revenue", "a]b", "x`y", "xor"];
+    let expr = tinyexpr::compile("[Gross Margin %] * Revenue / 100", &vars).unwrap().bind::<f64>().unwrap();
+    assert_eq!(expr.eval(&[40.0, 250.0, 0.0, 0.0, 0.0]).unwrap(), 100.0);
+    let expr = tinyexpr::compile("`Gross Margin %` + [Revenue] + [a]]b] + `x``y` + [xor]", &vars).unwrap().bind::<f64>().unwrap();
+    assert_eq!(expr.eval(&[1.0, 2.0, 3.0, 4.0, 5.0]).unwrap(), 15.0);
+
+    // quoted names go through the same lookup, builtins included
+    assert_eq!(tinyexpr::interp("[sqrt](16) + `pi` - pi").unwrap(), 4.0);
+    assert!(tinyexpr::compile("[Gross margin %]", &vars).is_err());
+    let options = tinyexpr::Options { case_insensitive: true, ..tinyexpr::Options::default() };
+    assert!(tinyexpr::compile_with("[gross margin %]", &vars, &options).is_ok());
+
+    match tinyexpr::compile("1 + [Gross Margin %", &vars) {
+        Err(tinyexpr::error::TinyExprError::Syntax(position, message)) => {
+            assert_eq!((position, message.as_str()), (4, "quoted name is not closed"));
+        },
+        _ => panic!("expected a syntax error"),
+    }
+    assert!(tinyexpr::compile("`a]]b`", &vars).is_err());
+    assert!(tinyexpr::compile("[]", &vars).is_err());
+}
+
+#[test]
+fn check_comments() {
+    assert_eq!(tinyexpr::interp("1 + 2 # three").unwrap(), 3.0);
+    assert_eq!(tinyexpr::interp("# leading\n1 + # first\n2 # second\n").unwrap(), 3.0);
+    assert_eq!(tinyexpr::interp("2 /* two */ * /* three */ 3").unwrap(), 6.0);
+    assert_eq!(tinyexpr::interp("/* a\n * b */ 4 /**/ / 2").unwrap(), 2.0);
+    assert_eq!(tinyexpr::interp("6 / /* not nested /* */ 3").unwrap(), 2.0);
+    let expr = tinyexpr::compile("[a # b] /* quoted names keep # and /* */", &["a # b"]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(expr.eval(&[5.0]).unwrap(), 5.0);
+
+    // positions still count the characters of comments
+    let syntax_error = |expr: &str| match tinyexpr::interp(expr) {
+        Err(tinyexpr::error::TinyExprError::Syntax(position, message)) => (position, message),
+        other => panic!("expected a syntax error, got {:?}", other),
+    };
+    assert_eq!(syntax_error("1 /* one */ 2"), (12, String::from("unexpected token")));
+    assert_eq!(syntax_error("# π\n1 + ?"), (9, String::from("unexpected token")));
+    assert_eq!(syntax_error("1 + /* open"), (4, String::from("comment is not closed")));
+    assert_eq!(syntax_error("1 + # to the end"), (16, String::from("unexpected end of expression")));
+}
+
+#[test]
+fn check_scripts() {
+    let script = tinyexpr::compile_script("a = x*2; b = a+1; a*b", &["x"]).unwrap();
+    assert_eq!(script.locals(), &["a", "b"]);
+    let script = script.bind::<f64>().unwrap();
+    assert_eq!(script.eval(&[3.0]).unwrap(), 42.0);
+    let result = script.run(&[1.0]).unwrap();
+    assert_eq!(result.value, 6.0);
+    assert_eq!(result.locals, vec![(String::from("a"), 2.0), (String::from("b"), 3.0)]);
+    assert_eq!(result.local("a"), Some(&2.0));
+    assert_eq!(result.local("x"), None);
+
+    // locals can be assigned again, and a trailing `;` is allowed
+    let script = tinyexpr::compile_script("total = 0; total = total + x; total = total * 2;", &["x"]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(script.run(&[5.0]).unwrap().locals, vec![(String::from("total"), 10.0)]);
+    assert_eq!(script.eval(&[5.0]).unwrap(), 10.0);
+
+    // constant statements are folded, assignments are still performed on each run
+    let script = tinyexpr::compile_script("r = rand(); r == r", &[]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(script.eval(&[]).unwrap(), 1.0);
+    let script = tinyexpr::compile_script("k = 2^10; k + 1", &[]).unwrap().bind::<tinyexpr::Rational>().unwrap();
+    assert_eq!(script.eval(&[]).unwrap(), tinyexpr::Rational::new(1025, 1));
+    assert_eq!(tinyexpr::compile_script("1 + 1", &[]).unwrap().bind::<f64>().unwrap().eval(&[]).unwrap(), 2.0);
+
+    let syntax_error = |source: &str| match tinyexpr::compile_script(source, &["x"]) {
+        Err(tinyexpr::error::TinyExprError::Syntax(position, message)) => (position, message),
+        other => panic!("expected a syntax error, got {:?}", other),
+    };
+    // a local is visible from the statement after its assignment
+    assert_eq!(syntax_error("a = a + 1"), (4, String::from("unexpected token")));
+    assert_eq!(syntax_error("b = 1; a + b; a = 2"), (7, String::from("unexpected token")));
+    assert_eq!(syntax_error("y = 1; x = 2"), (7, String::from("variables passed to the script cannot be assigned")));
+    assert_eq!(syntax_error("sin = 1; sin"), (0, String::from("builtin functions cannot be assigned")));
+    assert_eq!(syntax_error("xor = 1; 2"), (0, String::from("`xor` is a keyword")));
+    assert_eq!(syntax_error("a = 1, b = 2"), (7, String::from("unexpected token")));
+    assert_eq!(syntax_error("a = ; 1"), (4, String::from("unexpected token")));
+
+    // plain expressions have no statements
+    assert!(tinyexpr::compile("a = 1", &[]).is_err());
+    assert!(tinyexpr::compile("1; 2", &[]).is_err());
+    let script = tinyexpr::compile_script("x + 1", &["x"]).unwrap().bind::<f64>().unwrap();
+    assert!(script.eval(&[]).is_err());
+}
+
+#[test]
+fn check_user_functions() {
+    let script = tinyexpr::compile_script("f(x, y) = x^2 + y^2; g(t) = 2 * f(t, t + 1); g(x) + f(3, 4)", &["x"]).unwrap();
+    assert_eq!(script.bind::<f64>().unwrap().eval(&[1.0]).unwrap(), 35.0);
+
+    // bodies only see their parameters, and arguments are evaluated once
+    let script = tinyexpr::compile_script("sq(x) = x*x; a = 3; sq(a) + sq(x)", &["x"]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(script.eval(&[2.0]).unwrap(), 13.0);
+    let script = tinyexpr::compile_script("d(t) = t - t; d(rand())", &[]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(script.eval(&[]).unwrap(), 0.0);
+
+    // each body is stored once, so functions calling each other twice do not blow up the tree
+    let mut source = String::from("f0(x) = x + 1;");
+    for i in 1..40 {
+        source.push_str(&format!(" f{}(x) = f{}(x) + f{}(x);", i, i - 1, i - 1));
+    }
+    source.push_str(" f10(1)");
+    let script = tinyexpr::compile_script(&source, &[]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(script.eval(&[]).unwrap(), 2048.0);
+
+    // functions without parameters, redefinitions and calls like builtins
+    let script = tinyexpr::compile_script("k() = 7; h(t) = t + 1; h 2 + k + k(); h(t) = t; h(5)", &[]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(script.eval(&[]).unwrap(), 5.0);
+    let script = tinyexpr::compile_script("double(t) = 2t; double(4)", &[]);
+    assert!(script.is_err());
+    let options = tinyexpr::Options { implicit_multiplication: true, ..tinyexpr::Options::default() };
+    let script = tinyexpr::compile_script_with("double(t) = 2t; double(4)", &[], &options).unwrap().bind::<f64>().unwrap();
+    assert_eq!(script.eval(&[]).unwrap(), 8.0);
+
+    // definitions registered as strings work in plain expressions too
+    let options = tinyexpr::Options {
+        definitions: vec![String::from("hyp(a, b) = sqrt(a^2 + b^2)"), String::from("area(r) = pi * r^2")],
+        ..tinyexpr::Options::default()
+    };
+    let expr = tinyexpr::compile_with("hyp(x, 4) + area(1)", &["x"], &options).unwrap().bind::<f64>().unwrap();
+    assert_eq!(expr.eval(&[3.0]).unwrap(), 5.0 + std::f64::consts::PI);
+    assert_eq!(tinyexpr::compile_with("hyp(6, 8)", &[], &options).unwrap().bind::<f32>().unwrap().eval(&[]).unwrap(), 10.0);
+
+    // recursion ends through `if`, which only evaluates one branch, or is cut off
+    let script = tinyexpr::compile_script("fact(n) = if(n <= 1, 1, n * fact(n - 1)); fact(x)", &["x"]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(script.eval(&[10.0]).unwrap(), 3628800.0);
+    let script = tinyexpr::compile_script("fib(n) = if(n < 2, n, fib(n - 1) + fib(n - 2)); fib(15)", &[]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(script.eval(&[]).unwrap(), 610.0);
+    let script = tinyexpr::compile_script("f(n) = n * f(n - 1); f(3)", &[]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(script.eval(&[]).unwrap_err().to_string(), format!("calls nested deeper than {}", tinyexpr::MAX_CALL_DEPTH));
+    let script = tinyexpr::compile_script("f(n) = if(n, f(n - 1), 0); f(1000)", &[]).unwrap().bind::<f64>().unwrap();
+    assert!(script.eval(&[]).is_err());
+
+    // deep bodies are cut off before the stack of a thread of the default size runs out
+    let deep = std::thread::Builder::new().stack_size(2 << 20).spawn(|| {
+        let source = "f(n) = if(n <= 0, 0, 1 + (1 + (1 + (1 + (1 + f(n-1)))))); f(300)";
+        let script = tinyexpr::compile_script(source, &[]).unwrap();
+        let shallow = script.bind::<f64>().unwrap().eval(&[]).unwrap_err().to_string();
+        let exact = script.bind::<tinyexpr::Rational>().unwrap().eval(&[]).unwrap_err().to_string();
+        let nested = tinyexpr::compile_script("f(n) = if(n <= 0, 0, if(n > -1, let a = n in f(a - 1), 0)); f(250)", &[]).unwrap();
+        let bound = nested.bind::<tinyexpr::Rational>().unwrap().eval(&[]).unwrap_err().to_string();
+        (shallow, exact, bound)
+    }).unwrap().join().unwrap();
+    let message = format!("evaluation nested deeper than {}", tinyexpr::MAX_EVAL_DEPTH);
+    assert_eq!(deep, (message.clone(), message.clone(), message));
+
+    let syntax_error = |source: &str, options: &tinyexpr::Options| match tinyexpr::compile_script_with(source, &["x"], options) {
+        Err(tinyexpr::error::TinyExprError::Syntax(position, message)) => (position, message),
+        other => panic!("expected a syntax error, got {:?}", other),
+    };
+    let default = tinyexpr::Options::default();
+    assert_eq!(syntax_error("f(a) = a; f = 2", &default), (10, String::from("functions cannot be assigned")));
+    assert_eq!(syntax_error("f(t) = t + x; f(1)", &default), (11, String::from("`x` is not a parameter of `f`")));
+    assert_eq!(syntax_error("a = 2; g(t) = a * t; g(1)", &default), (14, String::from("`a` is not a parameter of `g`")));
+    assert_eq!(syntax_error("sin(t) = 1; sin(2)", &default), (0, String::from("builtin functions cannot be redefined")));
+    assert_eq!(syntax_error("f(a, a) = a; f(1, 2)", &default), (5, String::from("duplicate parameter `a`")));
+    assert_eq!(syntax_error("g(a, b, c, b) = a; 1", &default), (11, String::from("duplicate parameter `b`")));
+    assert_eq!(syntax_error("Max(a, b) = a; 1", &default), (0, String::from("builtin functions cannot be redefined")));
+    assert_eq!(syntax_error("x(a) = a; 1", &default), (0, String::from("variables cannot be redefined as functions")));
+    assert_eq!(syntax_error("f(a, b, c, d, e, g, h, i) = 1; 1", &default), (0, String::from("functions take at most 7 parameters")));
+    assert_eq!(syntax_error("f(a) = a", &default), (8, String::from("a script needs a statement with a value")));
+    assert_eq!(syntax_error("g(a, b) = a; g(1)", &default), (16, String::from("unexpected token")));
+    let broken = tinyexpr::Options { definitions: vec![String::from("f(a) = a +")], ..tinyexpr::Options::default() };
+    assert_eq!(syntax_error("1", &broken), (10, String::from("unexpected end of expression in definition 1")));
+    let broken = tinyexpr::Options { definitions: vec![String::from("2 + 2")], ..tinyexpr::Options::default() };
+    assert_eq!(syntax_error("1", &broken), (0, String::from("expected a function definition in definition 1")));
+}
+
+#[test]
+fn check_let_bindings() {
+    let roots = tinyexpr::compile("let d = b^2 - 4*a*c in (-b + sqrt(d)) / (2*a)", &["a", "b", "c"]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(roots.eval(&[1.0, -3.0, 2.0]).unwrap(), 2.0);
+    assert_eq!(roots.eval(&[1.0, 2.0, 1.0]).unwrap(), -1.0);
+
+    // several bindings, nesting and shadowing
+    assert_eq!(tinyexpr::interp("let a = 2, b = a + 1 in a * b").unwrap(), 6.0);
+    assert_eq!(tinyexpr::interp("let a = 2 in let a = a * 10 in a + 1").unwrap(), 21.0);
+    assert_eq!(tinyexpr::interp("(let a = 2 in a) + 1").unwrap(), 3.0);
+    assert_eq!(tinyexpr::interp("1 + let a = 2 in a * 3").unwrap(), 7.0);
+    assert_eq!(tinyexpr::interp("max(let a = 2 in a, 3, let pi = 4 in pi)").unwrap(), 4.0);
+    assert_eq!(tinyexpr::interp("|let a = -2 in a|").unwrap(), 2.0);
+    let expr = tinyexpr::compile("(let x = x + 1 in x * x) + x", &["x"]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(expr.eval(&[2.0]).unwrap(), 11.0);
+
+    // each binding is evaluated once per evaluation
+    let expr = tinyexpr::compile("let r = rand() in r - r", &[]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(expr.eval(&[]).unwrap(), 0.0);
+    let expr = tinyexpr::compile("let r = rand() in r", &[]).unwrap().bind::<f64>().unwrap();
+    tinyexpr::seed_random(7);
+    let first = expr.eval(&[]).unwrap();
+    assert_ne!(expr.eval(&[]).unwrap(), first);
+
+    // in scripts and function bodies
+    let script = tinyexpr::compile_script("f(t) = let u = t + 1 in u * u; s = let v = 2 in f(v); s + f(0)", &[]).unwrap();
+    assert_eq!(script.locals(), &["s"]);
+    assert_eq!(script.bind::<f64>().unwrap().eval(&[]).unwrap(), 10.0);
+    let script = tinyexpr::compile_script("v = 5; (let v = 1 in v) + v", &[]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(script.eval(&[]).unwrap(), 6.0);
+
+    let syntax_error = |expr: &str| match tinyexpr::interp(expr) {
+        Err(tinyexpr::error::TinyExprError::Syntax(position, message)) => (position, message),
+        other => panic!("expected a syntax error, got {:?}", other),
+    };
+    assert_eq!(syntax_error("let a = 1"), (9, String::from("expected `in` or `,` after a `let` binding")));
+    assert_eq!(syntax_error("let 2 = 1 in 2"), (4, String::from("expected a name after `let`")));
+    assert_eq!(syntax_error("let in = 2 in 3"), (4, String::from("`in` is a keyword")));
+    assert_eq!(syntax_error("let let = 1 in 2"), (4, String::from("`let` is a keyword")));
+    assert_eq!(syntax_error("let xor = 1 in 2"), (4, String::from("`xor` is a keyword")));
+    assert_eq!(syntax_error("let a + 1 in a"), (6, String::from("unexpected token")));
+    assert_eq!(syntax_error("(let a = 1 in a) + a"), (19, String::from("unexpected token")));
+    assert_eq!(syntax_error("let a = a in 1"), (8, String::from("unexpected token")));
+    let expr = tinyexpr::compile("[let] + [in]", &["let", "in"]).unwrap().bind::<f64>().unwrap();
+    assert_eq!(expr.eval(&[1.0, 2.0]).unwrap(), 3.0);
+}
+
+#[test]
+fn check_number_types() {
+    assert_eq!(tinyexpr::interp_as::<f32>("2+2*2").unwrap(), 6.0f32);
+    assert_eq!(tinyexpr::interp_as::<f32>("1/3").unwrap(), 1.0f32 / 3.0);
+    assert_eq!(tinyexpr::interp_as::<f32>("pi").unwrap(), std::f32::consts::PI);
+    assert_eq!(tinyexpr::interp_as::<f32>("sqrt(2)").unwrap(), 2.0f32.sqrt());
+    assert_eq!(tinyexpr::interp_as::<f64>("sqrt(2)").unwrap(), tinyexpr::interp("sqrt(2)").unwrap());
+}
+
+#[test]
+fn check_complex() {
+    use tinyexpr::Complex;
+    let close = |a: Complex, b: Complex| (a.re - b.re).abs() < 1e-12 && (a.im - b.im).abs() < 1e-12;
+
+    assert_eq!(tinyexpr::interp_as::<Complex>("sqrt(-1)").unwrap(), Complex::new(0.0, 1.0));
+    assert_eq!(tinyexpr::interp_as::<Complex>("i*i").unwrap(), Complex::new(-1.0, 0.0));
+    assert_eq!(tinyexpr::interp_as::<Complex>("(1+2*i)*(3-i)").unwrap(), Complex::new(5.0, 5.0));
+    assert!(close(tinyexpr::interp_as::<Complex>("exp(i*pi)").unwrap(), Complex::new(-1.0, 0.0)));
+    assert!(close(tinyexpr::interp_as::<Complex>("ln(-2)").unwrap(), Complex::new(2.0f64.ln(), std::f64::consts::PI)));
+    assert!(close(tinyexpr::interp_as::<Complex>("sin(asin(2))").unwrap(), Complex::new(2.0, 0.0)));
+    assert!(close(tinyexpr::interp_as::<Complex>("cos(acos(3+i))").unwrap(), Complex::new(3.0, 1.0)));
+    assert!(close(tinyexpr::interp_as::<Complex>("tan(atan(1-2*i))").unwrap(), Complex::new(1.0, -2.0)));
+    assert!(close(tinyexpr::interp_as::<Complex>("atan2(1, -1)").unwrap(), Complex::new(3.0 * std::f64::consts::FRAC_PI_4, 0.0)));
+    assert!(close(tinyexpr::interp_as::<Complex>("pow(i, 2)").unwrap(), Complex::new(-1.0, 0.0)));
+    assert!(close(tinyexpr::interp_as::<Complex>("log10(-100)").unwrap(), Complex::new(2.0, std::f64::consts::PI / std::f64::consts::LN_10)));
+    assert_eq!(tinyexpr::interp_as::<Complex>("re(3+4*i)").unwrap(), Complex::new(3.0, 0.0));
+    assert_eq!(tinyexpr::interp_as::<Complex>("im(3+4*i)").unwrap(), Complex::new(4.0, 0.0));
+    assert_eq!(tinyexpr::interp_as::<Complex>("abs(3+4*i)").unwrap(), Complex::new(5.0, 0.0));
+    assert_eq!(tinyexpr::interp_as::<Complex>("arg(-1)").unwrap(), Complex::new(std::f64::consts::PI, 0.0));
+    assert_eq!(tinyexpr::interp_as::<Complex>("conj(3+4*i)").unwrap(), Complex::new(3.0, -4.0));
+    assert_eq!(tinyexpr::interp_as::<Complex>("sqrt(-4)").unwrap().to_string(), "0+2i");
+    assert_eq!(tinyexpr::interp_as::<Complex>("0^0").unwrap(), Complex::new(1.0, 0.0));
+    assert_eq!(tinyexpr::interp_as::<Complex>("0^2").unwrap(), Complex::new(0.0, 0.0));
+    assert_eq!(tinyexpr::interp_as::<Complex>("0^(0.5+i)").unwrap(), Complex::new(0.0, 0.0));
+    assert_eq!(tinyexpr::interp_as::<Complex>("0^-2").unwrap().re, f64::INFINITY);
+    assert!(tinyexpr::interp_as::<Complex>("0^i").unwrap().re.is_nan());
+    assert!(tinyexpr::interp_as::<Complex>("0^(-1+i)").unwrap().re.is_nan());
+
+    assert_eq!(tinyexpr::interp("re(2)+im(2)").unwrap(), 2.0);
+    assert_eq!(tinyexpr::interp("arg(-2)").unwrap(), std::f64::consts::PI);
+    assert!(tinyexpr::interp("i").is_err());
+}
+
+#[test]
+fn check_rational() {
+    use tinyexpr::Rational;
+
+    assert_eq!(tinyexpr::interp_as::<Rational>("1/3*3").unwrap(), Rational::new(1, 1));
+    assert_eq!(tinyexpr::interp_as::<Rational>("0.1+0.2").unwrap(), Rational::new(3, 10));
+    assert_eq!(tinyexpr::interp_as::<Rational>("1/3+1/6").unwrap().to_string(), "1/2");
+    assert_eq!(tinyexpr::interp_as::<Rational>("-7/2").unwrap().to_string(), "-7/2");
+    assert_eq!(tinyexpr::interp_as::<Rational>("(2/3)^-2").unwrap(), Rational::new(9, 4));
+    assert_eq!(tinyexpr::interp_as::<Rational>("2^100").unwrap().to_string(), "1267650600228229401496703205376");
+    assert_eq!(tinyexpr::interp_as::<Rational>("2^100/2^99").unwrap(), Rational::new(2, 1));
+    assert_eq!(tinyexpr::interp_as::<Rational>("7.5%2").unwrap(), Rational::new(3, 2));
+    assert_eq!(tinyexpr::interp_as::<Rational>("-7.5%2").unwrap(), Rational::new(-3, 2));
+    assert_eq!(tinyexpr::interp_as::<Rational>("floor(-7/2)").unwrap(), Rational::new(-4, 1));
+    assert_eq!(tinyexpr::interp_as::<Rational>("ceil(-7/2)").unwrap(), Rational::new(-3, 1));
+    assert_eq!(tinyexpr::interp_as::<Rational>("abs(-7/2)").unwrap(), Rational::new(7, 2));
+    assert_eq!(tinyexpr::interp_as::<Rational>("1/3").unwrap().to_f64(), 1.0 / 3.0);
+    assert_eq!(tinyexpr::interp_as::<Rational>("0.1").unwrap().to_f64(), 0.1);
+    assert_eq!(tinyexpr::interp_as::<Rational>("2^-1074").unwrap().to_f64(), 5e-324);
+
+    assert_eq!(tinyexpr::interp_as::<Rational>("sin(1)").unwrap_err().to_string(), "`sin` is not supported by this number type");
+    assert!(tinyexpr::interp_as::<Rational>("2^0.5").is_err());
+    assert_eq!(tinyexpr::interp_as::<Rational>("1/(3-3)").unwrap_err().to_string(), "division by zero");
+
+    // powers are bounded in size, except where the result stays small
+    assert_eq!(tinyexpr::interp_as::<Rational>("2^100000000").unwrap_err().to_string(), "result of pow is too large");
+    assert!(tinyexpr::interp_as::<Rational>("(1/3)^-100000000").is_err());
+    assert_eq!(tinyexpr::interp_as::<Rational>("(-1)^100000001").unwrap(), Rational::new(-1, 1));
+    assert_eq!(tinyexpr::interp_as::<Rational>("2^60000").unwrap().to_f64(), f64::INFINITY);
+
+    // arithmetic on powers near the limit reduces fractions in reasonable time, even unoptimized
+    use tinyexpr::Number;
+    let start = std::time::Instant::now();
+    let sum = tinyexpr::interp_as::<Rational>("(3/2)^40000 + 1").unwrap();
+    assert_eq!(sum.clone().sub(Rational::new(1, 1)).unwrap(), tinyexpr::interp_as::<Rational>("3^40000/2^40000").unwrap());
+    assert_eq!(tinyexpr::interp_as::<Rational>("(3/2)^40000 / (6/4)^40000").unwrap(), Rational::new(1, 1));
+    assert!(start.elapsed() < std::time::Duration::from_secs(10), "took {:?}", start.elapsed());
+}
+
+#[test]
+fn check_variables() {
+    let expr = tinyexpr::compile("x*y+1", &["x", "y"]).unwrap();
+    assert_eq!(expr.bind::<f64>().unwrap().eval(&[2.0, 3.0]).unwrap(), 7.0);
+    assert_eq!(expr.bind::<f32>().unwrap().eval(&[0.5, 4.0]).unwrap(), 3.0);
+    assert!(expr.bind::<f64>().unwrap().eval(&[2.0]).is_err());
+    assert!(tinyexpr::compile("x*z", &["x", "y"]).is_err());
+}
+
+#[test]
+fn check_interval() {
+    use tinyexpr::Interval;
+
+    let eval = |e: &str, x: Interval| tinyexpr::compile(e, &["x"]).unwrap().bind::<Interval>().unwrap().eval(&[x]).unwrap();
+    let x = Interval::new(0.0, 10.0);
+
+    assert_eq!(eval("x*x-2*x", x), Interval::new(-20.0, 100.0));
+    assert_eq!(eval("x^2", Interval::new(-2.0, 3.0)), Interval::new(0.0, eval("x^2", Interval::point(3.0)).hi));
+    assert_eq!(eval("x^2", x).lo, 0.0);
+    assert_eq!(eval("x^3", x).lo, 0.0);
+    assert_eq!(eval("x^2", Interval::new(-3.0, -1.0)).lo, 1f64.next_down().next_down());
+    assert!(eval("x^3", Interval::new(-3.0, 0.0)).lo < -27.0);
+    assert_eq!(eval("x+1", x), Interval::new(1.0, 11.0));
+    assert_eq!(eval("sin(x)", x), Interval::new(-1.0, 1.0));
+    let sin = eval("sin(x)", Interval::new(0.0, 1.0));
+    assert!(sin.contains(0.0) && sin.contains(1f64.sin()) && sin.lo > -1e-300 && sin.hi < 0.85);
+    assert_eq!(eval("1/x", x), Interval::new(0.1f64.next_down(), f64::INFINITY));
+    assert_eq!(eval("1/(x-5)", x), Interval::new(f64::NEG_INFINITY, f64::INFINITY));
+    assert_eq!(eval("-1/x", x).hi, -0.1f64.next_down());
+    assert!(tinyexpr::compile("1/(x-x)", &["x"]).unwrap().bind::<Interval>().unwrap().eval(&[Interval::point(1.0)]).is_err());
+    assert_eq!(eval("sqrt(x)", Interval::new(-4.0, 4.0)), Interval::new(0.0, 2.0));
+    let cbrt = eval("cbrt(x)", Interval::new(-8.0, 27.0));
+    assert!(cbrt.contains(-2.0) && cbrt.contains(3.0) && cbrt.lo > -2.0001 && cbrt.hi < 3.0001);
+
+    // 0.1 is not exactly representable, the result has to enclose 0.3
+    let tenths = eval("0.1+0.2", x);
+    assert!(tenths.lo < 0.3 && 0.3 < tenths.hi);
+
+    // sampled values of every expression lie within the computed bounds
+    let exprs = ["x^3-4*x", "sin(x)*cos(x)", "exp(-x)*x", "pow(x, 0.5)+ln(x+1)", "atan2(x, 3)", "tan(x/20)", "x%3", "abs(x-5)/(x+1)", "cosh(x-5)", "cbrt(x-5)"];
+    for e in exprs.iter() {
+        let bounds = eval(e, x);
+        let f = tinyexpr::compile(e, &["x"]).unwrap().bind::<f64>().unwrap();
+        for i in 0..=1000 {
+            let v = f.eval(&[i as f64 / 100.0]).unwrap();
+            assert!(bounds.contains(v), "{} = {} not in {}", e, v, bounds);
+        }
+    }
+}
+
+#[test]
+fn check_decimal() {
+    use tinyexpr::Decimal;
+    use tinyexpr::decimal::{Down, HalfUp};
+    use tinyexpr::Number;
+
+    let dec = |e: &str| tinyexpr::interp_as::<Decimal<2>>(e).unwrap().to_string();
+    assert_eq!(tinyexpr::interp_as::<Decimal<2>>("0.1+0.2").unwrap(), Decimal::<2>::parse("0.3").unwrap());
+    assert_eq!(dec("0.1+0.2"), "0.30");
+    assert_eq!(dec("10/3"), "3.33");
+    assert_eq!(dec("-10/3"), "-3.33");
+    assert_eq!(dec("2.675*1"), "2.68");
+    assert_eq!(dec("0.125*1"), "0.12");
+    assert_eq!(dec("0.135*1"), "0.14");
+    assert_eq!(dec("0.125"), "0.12");
+    assert_eq!(dec("1.05^2"), "1.10");
+    assert_eq!(dec("2^-2"), "0.25");
+    assert_eq!(dec("7.5%2"), "1.50");
+    assert_eq!(dec("floor(-2.5)"), "-3.00");
+    assert_eq!(dec("ceil(-2.5)"), "-2.00");
+    assert_eq!(dec("-0.05"), "-0.05");
+    assert_eq!(tinyexpr::interp_as::<Decimal<2, HalfUp>>("0.125").unwrap().to_string(), "0.13");
+    assert_eq!(tinyexpr::interp_as::<Decimal<2, HalfUp>>("-0.125").unwrap().to_string(), "-0.13");
+    assert_eq!(tinyexpr::interp_as::<Decimal<2, Down>>("0.129").unwrap().to_string(), "0.12");
+    assert_eq!(tinyexpr::interp_as::<Decimal<2, Down>>("-2/3").unwrap().to_string(), "-0.66");
+    assert_eq!(tinyexpr::interp_as::<Decimal<4>>("1/8").unwrap().to_string(), "0.1250");
+    assert_eq!(tinyexpr::interp_as::<Decimal<0>>("7/2").unwrap().to_string(), "4");
+    assert_eq!(tinyexpr::interp_as::<Decimal<2>>("1/3").unwrap().to_f64(), 0.33);
+
+    // large powers are rounded at each step and bounded in size
+    assert_eq!(dec("0.99^100000000"), "0.00");
+    assert_eq!(dec("1^100000000"), "1.00");
+    assert_eq!(dec("1.5^-100000000"), "0.00");
+    assert_eq!(tinyexpr::interp_as::<Decimal<2>>("1.01^100000000").unwrap_err().to_string(), "result of pow is too large");
+    assert_eq!(dec("1.01^100"), "2.70");
+
+    assert!(tinyexpr::interp_as::<Decimal<2>>("1/0").is_err());
+    assert!(tinyexpr::interp_as::<Decimal<2>>("sqrt(2)").is_err());
+}
+
 #[test]
 #[should_panic]
 fn parse_error()